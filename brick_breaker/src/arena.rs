@@ -0,0 +1,102 @@
+use bevy::{
+    prelude::*,
+    sprite::{MaterialMesh2dBundle, Mesh2dHandle},
+};
+
+use crate::assets::GameAssets;
+use crate::components::{Collider, Wall};
+use crate::playfield::Playfield;
+
+const WALL_THICKNESS: f32 = 40.;
+const WALL_COLOR: Color = Color::srgb(0.4, 0.4, 0.45);
+const BUMPER_LENGTH: f32 = 70.;
+const BUMPER_THICKNESS: f32 = 10.;
+
+// A solid box the ball bounces off, rotated by `angle` radians around its center
+#[derive(Debug, Clone, Copy)]
+pub struct WallSpec {
+    pub position: Vec2,
+    pub size: Vec2,
+    pub angle: f32,
+}
+
+// The walls and obstacles that make up a level's play area. Anything not covered
+// by a wall is open, so the ball can leave the playfield through it
+#[derive(Resource, Debug, Clone, Default)]
+pub struct Arena {
+    pub walls: Vec<WallSpec>,
+}
+
+impl Arena {
+    // Walls on the left, right and top of the playfield with the bottom left open,
+    // plus angled bumpers in the top corners so the ball can't get stuck in them
+    pub fn classic(playfield: &Playfield) -> Self {
+        let corner_offset = BUMPER_LENGTH / 2. * std::f32::consts::FRAC_1_SQRT_2;
+        Arena::default()
+            .with_wall(
+                Vec2::new(playfield.left() - WALL_THICKNESS / 2., 0.),
+                Vec2::new(WALL_THICKNESS, playfield.height + WALL_THICKNESS * 2.),
+            )
+            .with_wall(
+                Vec2::new(playfield.right() + WALL_THICKNESS / 2., 0.),
+                Vec2::new(WALL_THICKNESS, playfield.height + WALL_THICKNESS * 2.),
+            )
+            .with_wall(
+                Vec2::new(0., playfield.top() + WALL_THICKNESS / 2.),
+                Vec2::new(playfield.width + WALL_THICKNESS * 2., WALL_THICKNESS),
+            )
+            .with_bumper(
+                Vec2::new(playfield.left() + corner_offset, playfield.top() - corner_offset),
+                BUMPER_LENGTH,
+                std::f32::consts::FRAC_PI_4,
+            )
+            .with_bumper(
+                Vec2::new(playfield.right() - corner_offset, playfield.top() - corner_offset),
+                BUMPER_LENGTH,
+                -std::f32::consts::FRAC_PI_4,
+            )
+    }
+
+    pub fn with_wall(mut self, position: Vec2, size: Vec2) -> Self {
+        self.walls.push(WallSpec {
+            position,
+            size,
+            angle: 0.,
+        });
+        self
+    }
+
+    pub fn with_bumper(mut self, position: Vec2, length: f32, angle: f32) -> Self {
+        self.walls.push(WallSpec {
+            position,
+            size: Vec2::new(length, BUMPER_THICKNESS),
+            angle,
+        });
+        self
+    }
+}
+
+pub fn spawn_arena(
+    commands: &mut Commands,
+    assets: &mut GameAssets,
+    materials: &mut Assets<ColorMaterial>,
+    arena: &Arena,
+) {
+    let material = assets.material(materials, WALL_COLOR);
+    for wall in arena.walls.iter() {
+        commands.spawn((
+            MaterialMesh2dBundle {
+                mesh: Mesh2dHandle(assets.square.clone()),
+                material: material.clone(),
+                transform: Transform {
+                    translation: wall.position.extend(0.0),
+                    rotation: Quat::from_rotation_z(wall.angle),
+                    scale: wall.size.extend(1.0),
+                },
+                ..default()
+            },
+            Wall,
+            Collider,
+        ));
+    }
+}
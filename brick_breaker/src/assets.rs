@@ -0,0 +1,47 @@
+use std::collections::HashMap;
+
+use bevy::prelude::*;
+
+use crate::components::BrickKind;
+
+// Meshes and materials shared by everything drawn with them, so each is only made once
+// however many bricks there are or however often a level is rebuilt, and entities that look
+// the same can be batched together when they're drawn
+#[derive(Resource)]
+pub struct GameAssets {
+    // Unit sized, entities are sized with their transform's scale
+    pub square: Handle<Mesh>,
+    pub circle: Handle<Mesh>,
+    // A material for each color anything has been drawn in
+    materials: HashMap<[u8; 4], Handle<ColorMaterial>>,
+}
+
+impl FromWorld for GameAssets {
+    fn from_world(world: &mut World) -> Self {
+        let mut meshes = world.resource_mut::<Assets<Mesh>>();
+        let square = meshes.add(Rectangle::new(1.0, 1.0));
+        let circle = meshes.add(Circle { radius: 1.0 });
+
+        let mut assets = Self {
+            square,
+            circle,
+            materials: HashMap::new(),
+        };
+        // Every kind of brick's own color is ready before the first level
+        let mut materials = world.resource_mut::<Assets<ColorMaterial>>();
+        for kind in BrickKind::ALL {
+            assets.material(&mut materials, kind.color());
+        }
+        assets
+    }
+}
+
+impl GameAssets {
+    // The material for a color, made the first time it's needed
+    pub fn material(&mut self, materials: &mut Assets<ColorMaterial>, color: Color) -> Handle<ColorMaterial> {
+        self.materials
+            .entry(color.to_srgba().to_u8_array())
+            .or_insert_with(|| materials.add(color))
+            .clone()
+    }
+}
@@ -0,0 +1,301 @@
+// Checks a level can be cleared by letting the autopilot play it headlessly with a run of
+// different seeds, for example before accepting a new level:
+//
+//   cargo run --release --bin level_check -- levels/fortress.ron --runs 50
+//
+// Options (all optional apart from the level file):
+//   --runs <n>               Number of seeds to play, defaults to 20
+//   --seed <n>               Seed of the first run, each run after it adds one, defaults to 0
+//   --time-limit <seconds>   Game time each run gets to clear the level, defaults to 600
+//   --min-clear-rate <rate>  Fail unless at least this fraction of runs clear the level, defaults to 0.9
+//   --max-median-time <seconds>  Fail if the median clear time is longer than this
+//   --json                   Print the results as JSON instead of text
+//
+// Exits with 0 if the level passes, 1 if it fails a threshold and 2 if it couldn't be checked.
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+use bevy::prelude::*;
+use serde::Serialize;
+
+use brick_breaker::components::{BrickKind, GridCell, Health, Lives, Screen};
+use brick_breaker::controller::{Autopilot, ControllerSetup};
+use brick_breaker::events::{BallLost, BrickDamaged, BrickDestroyed};
+use brick_breaker::level::{Level, LevelGrid};
+use brick_breaker::playfield::Playfield;
+use brick_breaker::{headless_app, BrickBreakerPlugin, HEADLESS_FRAME};
+
+struct Options {
+    level: PathBuf,
+    runs: u32,
+    seed: u64,
+    time_limit: f32,
+    min_clear_rate: f32,
+    max_median_time: Option<f32>,
+    json: bool,
+}
+
+// What happened in one run
+#[derive(Resource, Default)]
+struct RunLog {
+    cells: HashMap<Entity, GridCell>,
+    hit: HashSet<GridCell>,
+    balls_lost: u32,
+}
+
+struct RunResult {
+    // Seconds of game time it took, `None` if it ran out of time
+    clear_time: Option<f32>,
+    hit: HashSet<GridCell>,
+    balls_lost: u32,
+}
+
+#[derive(Serialize)]
+struct UnhitBrick {
+    column: u32,
+    row: u32,
+    kind: BrickKind,
+}
+
+#[derive(Serialize)]
+struct Report {
+    level: String,
+    runs: u32,
+    first_seed: u64,
+    time_limit: f32,
+    cleared: u32,
+    clear_rate: f32,
+    // Over the runs that cleared the level
+    median_clear_time: Option<f32>,
+    balls_lost_per_run: f32,
+    // Breakable bricks that no run ever managed to hit
+    never_hit: Vec<UnhitBrick>,
+    // Why the level failed, empty if it passed
+    failures: Vec<String>,
+}
+
+fn main() -> ExitCode {
+    let options = match parse_options(std::env::args().skip(1)) {
+        Ok(options) => options,
+        Err(error) => {
+            eprintln!("{}", error);
+            return ExitCode::from(2);
+        }
+    };
+
+    let level = match Level::load(&options.level) {
+        Ok(level) => level,
+        Err(error) => {
+            eprintln!("{}", error);
+            return ExitCode::from(2);
+        }
+    };
+    if let Err(error) = level.validate(&LevelGrid::new(&Playfield::default())) {
+        eprintln!("{} can't be played: {}", options.level.display(), error);
+        return ExitCode::from(2);
+    }
+
+    let results: Vec<RunResult> = (0..options.runs)
+        .map(|index| play(&level, options.seed + index as u64, options.time_limit))
+        .collect();
+    let report = build_report(&options, &level, &results);
+
+    if options.json {
+        println!("{}", serde_json::to_string_pretty(&report).unwrap());
+    } else {
+        print_report(&report);
+    }
+
+    if report.failures.is_empty() {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    }
+}
+
+fn parse_options(mut args: impl Iterator<Item = String>) -> Result<Options, String> {
+    let mut level = None;
+    let mut options = Options {
+        level: PathBuf::new(),
+        runs: 20,
+        seed: 0,
+        time_limit: 600.,
+        min_clear_rate: 0.9,
+        max_median_time: None,
+        json: false,
+    };
+
+    while let Some(arg) = args.next() {
+        if arg == "--json" {
+            options.json = true;
+            continue;
+        }
+        if !arg.starts_with("--") {
+            level = Some(PathBuf::from(arg));
+            continue;
+        }
+
+        let value = args.next().ok_or_else(|| format!("{} needs a value", arg))?;
+        let integer = || value.parse::<u64>().map_err(|_| format!("{} needs a whole number, got \"{}\"", arg, value));
+        let number = || value.parse::<f32>().map_err(|_| format!("{} needs a number, got \"{}\"", arg, value));
+        match arg.as_str() {
+            "--runs" => options.runs = integer()?.max(1) as u32,
+            "--seed" => options.seed = integer()?,
+            "--time-limit" => options.time_limit = number()?,
+            "--min-clear-rate" => options.min_clear_rate = number()?,
+            "--max-median-time" => options.max_median_time = Some(number()?),
+            _ => return Err(format!("unknown option {}", arg)),
+        }
+    }
+
+    options.level = level.ok_or("usage: level_check <level file> [options]")?;
+    Ok(options)
+}
+
+fn play(level: &Level, seed: u64, time_limit: f32) -> RunResult {
+    let mut app = headless_app(BrickBreakerPlugin {
+        seed: Some(seed),
+        level: Some(level.clone()),
+    });
+    app.insert_resource(ControllerSetup::new(Autopilot))
+        .init_resource::<RunLog>()
+        .add_systems(OnEnter(Screen::Playing), remember_cells.after(brick_breaker::spawn_level))
+        .add_systems(Update, record_hits);
+    app.finish();
+    app.cleanup();
+    app.world_mut()
+        .resource_mut::<NextState<Screen>>()
+        .set(Screen::Playing);
+
+    let frames = (time_limit / HEADLESS_FRAME.as_secs_f32()).ceil() as u32;
+    let mut clear_time = None;
+    for frame in 1..=frames {
+        app.update();
+
+        let world = app.world_mut();
+        if world.query_filtered::<(), With<Health>>().iter(world).next().is_none() {
+            clear_time = Some(frame as f32 * HEADLESS_FRAME.as_secs_f32());
+            break;
+        }
+    }
+
+    let log = app.world_mut().remove_resource::<RunLog>().unwrap_or_default();
+    RunResult {
+        clear_time,
+        hit: log.hit,
+        balls_lost: log.balls_lost,
+    }
+}
+
+fn remember_cells(bricks: Query<(Entity, &GridCell)>, mut log: ResMut<RunLog>, mut lives: ResMut<Lives>) {
+    log.cells = bricks.iter().map(|(entity, cell)| (entity, *cell)).collect();
+    // The autopilot keeps playing however many balls it loses, they're counted instead
+    lives.0 = u32::MAX;
+}
+
+fn record_hits(
+    mut damaged: EventReader<BrickDamaged>,
+    mut destroyed: EventReader<BrickDestroyed>,
+    mut lost: EventReader<BallLost>,
+    mut log: ResMut<RunLog>,
+) {
+    let bricks: Vec<Entity> = damaged
+        .read()
+        .map(|event| event.brick)
+        .chain(destroyed.read().map(|event| event.brick))
+        .collect();
+    for brick in bricks {
+        if let Some(&cell) = log.cells.get(&brick) {
+            log.hit.insert(cell);
+        }
+    }
+    log.balls_lost += lost.read().count() as u32;
+}
+
+fn build_report(options: &Options, level: &Level, results: &[RunResult]) -> Report {
+    let runs = results.len() as u32;
+    let mut clear_times: Vec<f32> = results.iter().filter_map(|result| result.clear_time).collect();
+    clear_times.sort_by(f32::total_cmp);
+    let cleared = clear_times.len() as u32;
+    let clear_rate = cleared as f32 / runs as f32;
+    let median_clear_time = match clear_times.len() {
+        0 => None,
+        count if count % 2 == 1 => Some(clear_times[count / 2]),
+        count => Some((clear_times[count / 2 - 1] + clear_times[count / 2]) / 2.),
+    };
+
+    let hit: HashSet<GridCell> = results.iter().flat_map(|result| result.hit.iter().copied()).collect();
+    let never_hit: Vec<UnhitBrick> = level
+        .breakable()
+        .filter(|brick| !hit.contains(&brick.cell()))
+        .map(|brick| UnhitBrick {
+            column: brick.column,
+            row: brick.row,
+            kind: brick.kind,
+        })
+        .collect();
+
+    let mut failures = Vec::new();
+    if clear_rate < options.min_clear_rate {
+        failures.push(format!(
+            "clear rate {:.1}% is under {:.1}%",
+            clear_rate * 100.,
+            options.min_clear_rate * 100.
+        ));
+    }
+    if let Some(max) = options.max_median_time {
+        match median_clear_time {
+            Some(median) if median > max => {
+                failures.push(format!("median clear time {:.1}s is over {:.1}s", median, max))
+            }
+            Some(_) => {}
+            None => failures.push("no run cleared the level to time".to_string()),
+        }
+    }
+
+    Report {
+        level: level.name.clone(),
+        runs,
+        first_seed: options.seed,
+        time_limit: options.time_limit,
+        cleared,
+        clear_rate,
+        median_clear_time,
+        balls_lost_per_run: results.iter().map(|result| result.balls_lost).sum::<u32>() as f32 / runs as f32,
+        never_hit,
+        failures,
+    }
+}
+
+fn print_report(report: &Report) {
+    println!(
+        "Level \"{}\": {} runs (seeds {}..{}), up to {:.0}s each",
+        report.level,
+        report.runs,
+        report.first_seed,
+        report.first_seed + report.runs as u64,
+        report.time_limit
+    );
+    println!();
+    println!("Cleared:             {}/{} ({:.1}%)", report.cleared, report.runs, report.clear_rate * 100.);
+    match report.median_clear_time {
+        Some(median) => println!("Median clear time:   {:.1}s", median),
+        None => println!("Median clear time:   -"),
+    }
+    println!("Balls lost per run:  {:.2}", report.balls_lost_per_run);
+    if report.never_hit.is_empty() {
+        println!("Never hit:           none");
+    } else {
+        println!("Never hit:           {} bricks", report.never_hit.len());
+        for brick in &report.never_hit {
+            println!("  column {:>2}, row {:>2} ({:?})", brick.column, brick.row, brick.kind);
+        }
+    }
+    println!();
+    if report.failures.is_empty() {
+        println!("PASS");
+    } else {
+        println!("FAIL: {}", report.failures.join(", "));
+    }
+}
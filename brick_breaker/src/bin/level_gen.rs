@@ -0,0 +1,127 @@
+// Generates a level from a seed and saves it as a level file, to play, edit or check:
+//
+//   cargo run --bin level_gen -- 1234 levels/generated.ron --stage 4 --no-mirror
+//
+// Without any of the layout options this saves exactly the given stage of an endless game
+// played with the same seed. Options (all optional apart from the seed and file):
+//   --stage <n>                   Endless mode stage to take the layout settings from, defaults to 1
+//   --density <fraction>          Chance of each cell getting a brick
+//   --depth <fraction>            Fraction of the grid's rows, from the top, that can have bricks
+//   --strong <fraction>           Chance of a brick being strong
+//   --indestructible <fraction>   Chance of a brick being indestructible
+//   --no-mirror                   Don't mirror the left half of the layout onto the right
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+use brick_breaker::generator::{endless_stage, generate, stage_seed, GeneratorParams};
+use brick_breaker::level::LevelGrid;
+use brick_breaker::playfield::Playfield;
+
+struct Options {
+    seed: u64,
+    path: PathBuf,
+    stage: u32,
+    // Layout settings that differ from the stage's
+    params: Option<GeneratorParams>,
+}
+
+fn main() -> ExitCode {
+    let options = match parse_options(std::env::args().skip(1)) {
+        Ok(options) => options,
+        Err(error) => {
+            eprintln!("{}", error);
+            return ExitCode::from(2);
+        }
+    };
+
+    let grid = LevelGrid::new(&Playfield::default());
+    // Seeded the same way either way, so changing a layout option changes only that
+    let level = match options.params {
+        Some(params) => {
+            let mut level = generate(stage_seed(options.seed, options.stage), &params, &grid);
+            level.name = format!("Seed {} stage {}", options.seed, options.stage + 1);
+            level
+        }
+        None => endless_stage(options.seed, options.stage, &grid),
+    };
+    if let Err(error) = level.save(&options.path) {
+        eprintln!("{}", error);
+        return ExitCode::FAILURE;
+    }
+
+    println!(
+        "Saved \"{}\" to {}: {} bricks, {} to break",
+        level.name,
+        options.path.display(),
+        level.bricks.len(),
+        level.breakable().count()
+    );
+    ExitCode::SUCCESS
+}
+
+fn parse_options(mut args: impl Iterator<Item = String>) -> Result<Options, String> {
+    let mut positional = Vec::new();
+    let mut stage = 1;
+    let mut changes: Vec<(String, String)> = Vec::new();
+    let mut mirror = true;
+
+    while let Some(arg) = args.next() {
+        if arg == "--no-mirror" {
+            mirror = false;
+            continue;
+        }
+        if !arg.starts_with("--") {
+            positional.push(arg);
+            continue;
+        }
+
+        let value = args.next().ok_or_else(|| format!("{} needs a value", arg))?;
+        match arg.as_str() {
+            "--stage" => {
+                stage = value
+                    .parse::<u32>()
+                    .ok()
+                    .filter(|&stage| stage >= 1)
+                    .ok_or_else(|| format!("--stage needs a stage number from 1, got \"{}\"", value))?
+            }
+            "--density" | "--depth" | "--strong" | "--indestructible" => changes.push((arg, value)),
+            _ => return Err(format!("unknown option {}", arg)),
+        }
+    }
+
+    let [seed, path]: [String; 2] = positional
+        .try_into()
+        .map_err(|_| "usage: level_gen <seed> <level file> [options]".to_string())?;
+    let seed = seed
+        .parse()
+        .map_err(|_| format!("the seed needs to be a whole number, got \"{}\"", seed))?;
+
+    let stage = stage - 1;
+    let params = if changes.is_empty() && mirror {
+        None
+    } else {
+        let mut params = GeneratorParams::for_stage(stage);
+        params.mirror = mirror;
+        for (arg, value) in changes {
+            let fraction = value
+                .parse::<f32>()
+                .ok()
+                .filter(|fraction| (0. ..=1.).contains(fraction))
+                .ok_or_else(|| format!("{} needs a number from 0 to 1, got \"{}\"", arg, value))?;
+            match arg.as_str() {
+                "--density" => params.density = fraction,
+                "--depth" => params.depth = fraction,
+                "--strong" => params.strong = fraction,
+                _ => params.indestructible = fraction,
+            }
+        }
+        Some(params)
+    };
+
+    Ok(Options {
+        seed,
+        path: path.into(),
+        stage,
+        params,
+    })
+}
@@ -0,0 +1,71 @@
+// Turns pixel art into a level file, so levels can be drawn in any paint tool:
+//
+//   cargo run --bin level_import -- ../images/levels/invader.png levels/invader.ron
+//
+// PNGs get one brick per pixel in the pixel's color, with transparent pixels left empty.
+// Anything else is read as ASCII art, one character per brick: `S` is a strong brick, `X` an
+// indestructible one, a space or `.` is empty and any other character is a standard brick.
+// The art can be up to 12x11 and is centered across the top of the grid.
+//
+// Options:
+//   --name <name>  Name of the level, defaults to the art's file name
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+use brick_breaker::import::import;
+use brick_breaker::level::LevelGrid;
+use brick_breaker::playfield::Playfield;
+
+fn main() -> ExitCode {
+    let mut paths = Vec::new();
+    let mut name = None;
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--name" => match args.next() {
+                Some(value) => name = Some(value),
+                None => {
+                    eprintln!("--name needs a value");
+                    return ExitCode::from(2);
+                }
+            },
+            _ if arg.starts_with("--") => {
+                eprintln!("unknown option {}", arg);
+                return ExitCode::from(2);
+            }
+            _ => paths.push(PathBuf::from(arg)),
+        }
+    }
+    let [art, output]: [PathBuf; 2] = match paths.try_into() {
+        Ok(paths) => paths,
+        Err(_) => {
+            eprintln!("usage: level_import <art file> <level file> [--name <name>]");
+            return ExitCode::from(2);
+        }
+    };
+    let name = name.unwrap_or_else(|| {
+        art.file_stem()
+            .map_or("Imported".to_string(), |stem| stem.to_string_lossy().into_owned())
+    });
+
+    let level = match import(&art, &name, &LevelGrid::new(&Playfield::default())) {
+        Ok(level) => level,
+        Err(error) => {
+            eprintln!("{}", error);
+            return ExitCode::FAILURE;
+        }
+    };
+    if let Err(error) = level.save(&output) {
+        eprintln!("{}", error);
+        return ExitCode::FAILURE;
+    }
+
+    println!(
+        "Saved \"{}\" to {}: {} bricks, {} to break",
+        level.name,
+        output.display(),
+        level.bricks.len(),
+        level.breakable().count()
+    );
+    ExitCode::SUCCESS
+}
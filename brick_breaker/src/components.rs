@@ -1,4 +1,9 @@
 use bevy::prelude::*;
+use common::title::GameScreen;
+use rand::rngs::StdRng;
+use serde::{Deserialize, Serialize};
+
+pub use common::components::{LastContact, Velocity};
 
 #[derive(Component)]
 pub struct Ball;
@@ -7,28 +12,148 @@ pub struct Ball;
 pub struct Paddle;
 
 #[derive(Component)]
-pub struct Brick;
+pub struct Brick(pub BrickKind);
+
+#[derive(Debug, PartialEq, Eq, Copy, Clone, Hash, Serialize, Deserialize)]
+pub enum BrickKind {
+    Standard,
+    Strong,
+    // Never breaks, and doesn't need breaking to clear the level
+    Indestructible,
+}
+
+impl BrickKind {
+    pub const ALL: [BrickKind; 3] = [BrickKind::Standard, BrickKind::Strong, BrickKind::Indestructible];
+
+    // The kind after this one in `ALL`, wrapping around
+    pub fn next(&self) -> BrickKind {
+        let index = BrickKind::ALL.iter().position(|kind| kind == self).unwrap();
+        BrickKind::ALL[(index + 1) % BrickKind::ALL.len()]
+    }
+
+    pub fn previous(&self) -> BrickKind {
+        let index = BrickKind::ALL.iter().position(|kind| kind == self).unwrap();
+        BrickKind::ALL[(index + BrickKind::ALL.len() - 1) % BrickKind::ALL.len()]
+    }
+
+    // Number of hits it takes to break the brick, `None` if it can't be broken
+    pub fn hits(&self) -> Option<u32> {
+        match self {
+            BrickKind::Standard => Some(1),
+            BrickKind::Strong => Some(2),
+            BrickKind::Indestructible => None,
+        }
+    }
 
+    // Points for breaking the brick
+    pub fn points(&self) -> u32 {
+        match self {
+            BrickKind::Standard => 10,
+            BrickKind::Strong => 30,
+            BrickKind::Indestructible => 0,
+        }
+    }
+
+    pub fn color(&self) -> Color {
+        match self {
+            BrickKind::Standard => Color::WHITE,
+            BrickKind::Strong => Color::srgb(0.6, 0.6, 0.6),
+            BrickKind::Indestructible => Color::srgb(0.75, 0.55, 0.2),
+        }
+    }
+}
+
+// Where a brick sits on the level grid
+#[derive(Component, Debug, PartialEq, Eq, Copy, Clone, Hash)]
+pub struct GridCell {
+    pub column: u32,
+    pub row: u32,
+}
+
+// Hits left before a brick breaks
 #[derive(Component)]
-pub struct Velocity(pub Vec3);
+pub struct Health(pub u32);
 
 #[derive(Component)]
-pub struct Collider;
+pub struct Wall;
 
-#[derive(Event, Default)]
-pub struct CollisionEvent;
+// Anything the ball bounces off. The collider is the entity's transform applied to a unit square
+#[derive(Component)]
+pub struct Collider;
 
-#[derive(Debug, PartialEq, Eq, Copy, Clone)]
-pub enum Collision {
-    Left,
-    Right,
-    Top,
-    Bottom,
+// Where the ball touched a collider. `normal` points from the collider towards the ball
+// and `depth` is how far the ball has sunk into it
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub struct Contact {
+    pub point: Vec2,
+    pub normal: Vec2,
+    pub depth: f32,
 }
 
+#[derive(Resource, Default)]
+pub struct Score(pub u32);
+
+// Balls left, the ball in play included
+#[derive(Resource)]
+pub struct Lives(pub u32);
+
+// All gameplay randomness comes from here so a seeded game always plays out the same
+#[derive(Resource)]
+pub struct GameRng(pub StdRng);
+
+// What `GameRng` was last seeded with
+#[derive(Resource)]
+pub struct GameSeed(pub u64);
+
+// Balls bounce off the bottom of the field instead of being lost, for testing
+#[derive(Resource, Default)]
+pub struct GodMode(pub bool);
+
+// Tells the balls apart, so a rewind can put each one back where it was
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BallId(pub u32);
+
+// The paddle is WIDE_PADDLE_WIDTH wide until the timer runs out
+#[derive(Component)]
+pub struct WidePaddle(pub Timer);
+
+// Which screen the game is showing
 #[derive(Debug, Clone, Copy, Default, Eq, PartialEq, Hash, States)]
+pub enum Screen {
+    #[default]
+    Title,
+    // The autopilot playing while nobody's around
+    Demo,
+    Playing,
+    // The level editor, and trying out the level being edited
+    Editor,
+    PlayTest,
+    // Typing a name in for a new high score
+    NameEntry,
+    Leaderboard,
+}
+
+impl GameScreen for Screen {
+    const TITLE: Self = Screen::Title;
+    const DEMO: Self = Screen::Demo;
+    const PLAYING: Self = Screen::Playing;
+    const NAME_ENTRY: Self = Screen::NameEntry;
+    const LEADERBOARD: Self = Screen::Leaderboard;
+}
+
+// Where the game is up to, whenever there's a level on screen
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq, Hash, SubStates)]
+#[source(Screen = Screen::Demo | Screen::Playing | Screen::PlayTest)]
 pub enum GameState {
+    // The ball is sitting on the paddle waiting to be launched
     #[default]
     Start,
     InGame,
+    Paused,
+    // Running back through the last few seconds of play
+    Rewinding,
+    // Every breakable brick is gone
+    LevelComplete,
+    // The last ball is gone
+    GameOver,
 }
\ No newline at end of file
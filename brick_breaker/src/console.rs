@@ -0,0 +1,476 @@
+use std::collections::VecDeque;
+use std::path::Path;
+use std::str::FromStr;
+
+use bevy::{
+    ecs::system::RunSystemOnce,
+    input::{
+        keyboard::{Key, KeyboardInput},
+        ButtonState, InputSystem,
+    },
+    prelude::*,
+};
+use rand::{rngs::StdRng, SeedableRng};
+
+use crate::assets::GameAssets;
+use crate::components::{Ball, GameRng, GameSeed, GameState, GodMode, Paddle, Screen, Velocity, WidePaddle};
+use crate::generator::{endless_stage, Endless};
+use crate::level::{Level, LevelGrid};
+use crate::playfield::Playfield;
+use crate::timescale::TimeScale;
+use crate::{
+    clear_level, launch_ball, spawn_ball, spawn_level, BALL_RADIUS, PADDLE_HEIGHT, WIDE_PADDLE_TIME, WIDE_PADDLE_WIDTH,
+};
+
+// Lines of output kept on screen
+const CONSOLE_LINES: usize = 16;
+// Commands remembered for Up and Down
+const CONSOLE_HISTORY: usize = 50;
+
+// Every command, and what it does, for `help` and tab completion
+const COMMANDS: [(&str, &str); 9] = [
+    ("help", "list the commands"),
+    ("spawn_ball", "launch another ball off the paddle"),
+    ("set_speed", "<speed>: change the speed of every ball in flight"),
+    ("load_level", "<stage or file>: start over on an endless stage, or a level file"),
+    ("god", "turn losing balls off or back on"),
+    ("give", "powerup <name>: give the paddle a power-up, `wide` for a wider paddle"),
+    ("seed", "[seed]: show the gameplay seed, or reseed with a new one"),
+    ("timescale", "<scale>: run the game from 0.1 to 4 times as fast, 1 for normal speed"),
+    ("clear", "clear the console"),
+];
+
+// A drop-down console for testing, opened and closed with the key under Escape (`). While
+// it's open the game doesn't see any keys, so typing doesn't move the paddle. Up and Down go
+// through earlier commands and Tab completes command names.
+pub struct ConsolePlugin;
+
+impl Plugin for ConsolePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Console>()
+            .add_systems(Startup, spawn_console)
+            .add_systems(PreUpdate, (console_input, run_commands).chain().after(InputSystem))
+            .add_systems(Update, show_console.run_if(resource_changed::<Console>));
+    }
+}
+
+#[derive(Resource, Default)]
+pub struct Console {
+    pub open: bool,
+    input: String,
+    history: Vec<String>,
+    // How far back through the history Up has gone, 0 being the last command
+    browsing: Option<usize>,
+    lines: VecDeque<String>,
+    // Commands entered but not run yet
+    pending: Vec<String>,
+}
+
+impl Console {
+    pub fn print(&mut self, line: impl Into<String>) {
+        self.lines.push_back(line.into());
+        while self.lines.len() > CONSOLE_LINES {
+            self.lines.pop_front();
+        }
+    }
+
+    fn submit(&mut self) {
+        let command = std::mem::take(&mut self.input);
+        self.browsing = None;
+        if command.trim().is_empty() {
+            return;
+        }
+        if self.history.last() != Some(&command) {
+            self.history.push(command.clone());
+            if self.history.len() > CONSOLE_HISTORY {
+                self.history.remove(0);
+            }
+        }
+        self.pending.push(command);
+    }
+
+    // Steps back through the history with `older`, or forward again back to an empty line
+    fn browse(&mut self, older: bool) {
+        if self.history.is_empty() {
+            return;
+        }
+        self.browsing = match (self.browsing, older) {
+            (None, true) => Some(0),
+            (Some(back), true) => Some((back + 1).min(self.history.len() - 1)),
+            (Some(0), false) | (None, false) => None,
+            (Some(back), false) => Some(back - 1),
+        };
+        self.input = match self.browsing {
+            Some(back) => self.history[self.history.len() - 1 - back].clone(),
+            None => String::new(),
+        };
+    }
+
+    // Completes the command name being typed as far as it can, listing the commands it could
+    // be when there's more than one
+    fn complete(&mut self) {
+        if self.input.contains(' ') {
+            return;
+        }
+        let matches: Vec<&str> = COMMANDS
+            .iter()
+            .map(|(name, _)| *name)
+            .filter(|name| name.starts_with(self.input.as_str()))
+            .collect();
+        match matches[..] {
+            [] => {}
+            [name] => self.input = format!("{} ", name),
+            [first, ..] => {
+                let common = matches.iter().fold(first.len(), |common, name| {
+                    first.bytes().zip(name.bytes()).take(common).take_while(|(a, b)| a == b).count()
+                });
+                self.input = first[..common].to_string();
+                self.print(matches.join("  "));
+            }
+        }
+    }
+}
+
+#[derive(Component)]
+struct ConsoleRoot;
+
+#[derive(Component)]
+struct ConsoleOutput;
+
+#[derive(Component)]
+struct ConsoleInput;
+
+fn spawn_console(mut commands: Commands) {
+    let text_style = TextStyle {
+        font_size: 18.0,
+        ..default()
+    };
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    width: Val::Percent(100.0),
+                    position_type: PositionType::Absolute,
+                    flex_direction: FlexDirection::Column,
+                    padding: UiRect::all(Val::Px(10.0)),
+                    row_gap: Val::Px(5.0),
+                    ..default()
+                },
+                background_color: BackgroundColor(Color::BLACK.with_alpha(0.85)),
+                // Over the top of everything else, overlays included
+                z_index: ZIndex::Global(1),
+                visibility: Visibility::Hidden,
+                ..default()
+            },
+            ConsoleRoot,
+        ))
+        .with_children(|parent| {
+            parent.spawn((TextBundle::from_section("", text_style.clone()), ConsoleOutput));
+            parent.spawn((TextBundle::from_section("", text_style), ConsoleInput));
+        });
+}
+
+fn console_input(
+    mut console: ResMut<Console>,
+    mut keyboard: ResMut<Events<KeyboardInput>>,
+    mut keys: ResMut<ButtonInput<KeyCode>>,
+    mut mouse: ResMut<ButtonInput<MouseButton>>,
+) {
+    if !console.open {
+        if keys.just_pressed(KeyCode::Backquote) {
+            console.open = true;
+            // Nothing typed so far this frame, the ` included, ends up in the console
+            keyboard.clear();
+            keys.reset_all();
+        }
+        return;
+    }
+
+    for event in keyboard.drain() {
+        if event.state != ButtonState::Pressed {
+            continue;
+        }
+        match event.logical_key {
+            _ if event.key_code == KeyCode::Backquote => console.open = false,
+            Key::Escape => console.open = false,
+            Key::Enter => console.submit(),
+            Key::Tab => console.complete(),
+            Key::ArrowUp => console.browse(true),
+            Key::ArrowDown => console.browse(false),
+            Key::Backspace => {
+                console.input.pop();
+            }
+            Key::Space => console.input.push(' '),
+            Key::Character(text) => console.input.extend(text.chars().filter(|c| !c.is_control())),
+            _ => {}
+        }
+    }
+    // The game carries on underneath, but without any of the keys that went to the console
+    keys.reset_all();
+    mouse.reset_all();
+}
+
+fn run_commands(world: &mut World) {
+    if world.resource::<Console>().pending.is_empty() {
+        return;
+    }
+    let pending = std::mem::take(&mut world.resource_mut::<Console>().pending);
+    for command in pending {
+        world.resource_mut::<Console>().print(format!("> {}", command));
+        let output = run_command(world, &command);
+        let mut console = world.resource_mut::<Console>();
+        for line in output.lines() {
+            console.print(line);
+        }
+    }
+}
+
+fn run_command(world: &mut World, command: &str) -> String {
+    let mut words = command.split_whitespace();
+    let Some(name) = words.next() else {
+        return String::new();
+    };
+    let arguments: Vec<&str> = words.collect();
+    let usage = || {
+        COMMANDS
+            .iter()
+            .find(|(command, _)| *command == name)
+            .map_or(String::new(), |(command, help)| format!("Usage: {} {}", command, help))
+    };
+
+    match name {
+        "help" => COMMANDS
+            .iter()
+            .map(|(command, help)| format!("{} {}", command, help))
+            .collect::<Vec<_>>()
+            .join("\n"),
+        "spawn_ball" => world.run_system_once(spawn_ball_command),
+        "set_speed" => match argument::<f32>(&arguments) {
+            Some(speed) if speed.is_finite() && speed > 0. => world.run_system_once_with(speed, set_speed),
+            Some(speed) => format!("{} isn't a speed", speed),
+            None => usage(),
+        },
+        "load_level" => match arguments[..] {
+            [level] => load_level(world, level),
+            _ => usage(),
+        },
+        "give" => match arguments[..] {
+            ["powerup", "wide"] => world.run_system_once(give_wide_paddle),
+            ["powerup", power_up] => format!("Unknown power-up {}", power_up),
+            _ => usage(),
+        },
+        "god" => {
+            let mut god_mode = world.resource_mut::<GodMode>();
+            god_mode.0 = !god_mode.0;
+            format!("God mode {}", if god_mode.0 { "on" } else { "off" })
+        }
+        "seed" => match arguments[..] {
+            [] => format!("Seed {}", world.resource::<GameSeed>().0),
+            _ => match argument(&arguments) {
+                Some(seed) => {
+                    world.insert_resource(GameRng(StdRng::seed_from_u64(seed)));
+                    world.insert_resource(GameSeed(seed));
+                    format!("Reseeded with {}", seed)
+                }
+                None => usage(),
+            },
+        },
+        "timescale" => match (argument(&arguments), world.get_resource_mut::<TimeScale>()) {
+            (Some(scale), Some(mut time_scale)) => match time_scale.set_scale(scale) {
+                true => format!("Time scale {}", time_scale.scale),
+                false => format!("{} isn't a time scale", scale),
+            },
+            (Some(_), None) => "The time scale can't be changed here".to_string(),
+            _ => usage(),
+        },
+        "clear" => {
+            world.resource_mut::<Console>().lines.clear();
+            String::new()
+        }
+        _ => format!("Unknown command {}, try help", name),
+    }
+}
+
+// The command's only argument
+fn argument<T: FromStr>(arguments: &[&str]) -> Option<T> {
+    match arguments {
+        [argument] => argument.parse().ok(),
+        _ => None,
+    }
+}
+
+fn spawn_ball_command(
+    mut commands: Commands,
+    mut assets: ResMut<GameAssets>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    paddle_query: Query<&Transform, With<Paddle>>,
+    screen: Res<State<Screen>>,
+    mut rng: ResMut<GameRng>,
+) -> String {
+    let Ok(paddle_transform) = paddle_query.get_single() else {
+        return "No game in progress".to_string();
+    };
+    let mut velocity = Velocity(Vec3::ZERO);
+    launch_ball(&mut velocity, &mut rng);
+    let position = paddle_transform.translation + Vec3::Y * (PADDLE_HEIGHT + BALL_RADIUS);
+    spawn_ball(&mut commands, &mut assets, &mut materials, position, velocity.0, *screen.get());
+    "Ball launched".to_string()
+}
+
+fn give_wide_paddle(mut commands: Commands, mut paddle_query: Query<(Entity, &mut Transform), With<Paddle>>) -> String {
+    let Ok((paddle, mut transform)) = paddle_query.get_single_mut() else {
+        return "No game in progress".to_string();
+    };
+    // Giving it again starts the time over
+    transform.scale.x = WIDE_PADDLE_WIDTH;
+    commands
+        .entity(paddle)
+        .insert(WidePaddle(Timer::from_seconds(WIDE_PADDLE_TIME, TimerMode::Once)));
+    "Paddle widened".to_string()
+}
+
+fn set_speed(In(speed): In<f32>, mut ball_query: Query<&mut Velocity, With<Ball>>) -> String {
+    let mut changed = 0;
+    for mut velocity in ball_query.iter_mut() {
+        if velocity.0 != Vec3::ZERO {
+            velocity.0 = velocity.0.normalize() * speed;
+            changed += 1;
+        }
+    }
+    match changed {
+        0 => "No balls in flight".to_string(),
+        _ => format!("Ball speed set to {}", speed),
+    }
+}
+
+// Starts the game over on a level: a number is that stage of endless mode, from the endless
+// seed if there is one and the gameplay seed if not, and anything else a level file
+fn load_level(world: &mut World, level: &str) -> String {
+    if world.get_resource::<State<GameState>>().is_none() {
+        return "No game in progress".to_string();
+    }
+    let grid = LevelGrid::new(world.resource::<Playfield>());
+
+    let level = match level.parse::<u32>() {
+        Ok(0) => return "Stages count from 1".to_string(),
+        Ok(stage) => match world.get_resource_mut::<Endless>() {
+            Some(mut endless) => endless.go_to_stage(stage - 1, &grid),
+            None => endless_stage(world.resource::<GameSeed>().0, stage - 1, &grid),
+        },
+        Err(_) => {
+            let loaded = Level::load(Path::new(level)).and_then(|level| level.validate(&grid).map(|_| level));
+            match loaded {
+                Ok(level) => level,
+                Err(error) => return error,
+            }
+        }
+    };
+
+    let name = level.name.clone();
+    world.insert_resource(level);
+    world.run_system_once(clear_level);
+    world.run_system_once(spawn_level);
+    format!("Loaded {}", name)
+}
+
+fn show_console(
+    console: Res<Console>,
+    mut root: Query<&mut Visibility, With<ConsoleRoot>>,
+    mut output: Query<&mut Text, (With<ConsoleOutput>, Without<ConsoleInput>)>,
+    mut input: Query<&mut Text, (With<ConsoleInput>, Without<ConsoleOutput>)>,
+) {
+    for mut visibility in root.iter_mut() {
+        *visibility = if console.open { Visibility::Inherited } else { Visibility::Hidden };
+    }
+    for mut text in output.iter_mut() {
+        text.sections[0].value = console.lines.iter().cloned().collect::<Vec<_>>().join("\n");
+    }
+    for mut text in input.iter_mut() {
+        text.sections[0].value = format!("> {}_", console.input);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn console_with_history(commands: &[&str]) -> Console {
+        let mut console = Console::default();
+        for command in commands {
+            console.input = command.to_string();
+            console.submit();
+        }
+        console
+    }
+
+    #[test]
+    fn browsing_stops_at_the_oldest_command_and_comes_back_to_an_empty_line() {
+        let mut console = console_with_history(&["god", "seed", "clear"]);
+        console.browse(true);
+        assert_eq!(console.input, "clear");
+        for _ in 0..5 {
+            console.browse(true);
+        }
+        assert_eq!(console.input, "god");
+
+        console.browse(false);
+        assert_eq!(console.input, "seed");
+        for _ in 0..5 {
+            console.browse(false);
+        }
+        assert_eq!(console.input, "");
+        assert_eq!(console.browsing, None);
+    }
+
+    #[test]
+    fn browsing_without_a_history_leaves_the_line_alone() {
+        let mut console = Console {
+            input: "spa".to_string(),
+            ..default()
+        };
+        console.browse(true);
+        assert_eq!(console.input, "spa");
+    }
+
+    #[test]
+    fn one_match_completes_the_command() {
+        let mut console = Console {
+            input: "spa".to_string(),
+            ..default()
+        };
+        console.complete();
+        assert_eq!(console.input, "spawn_ball ");
+        assert!(console.lines.is_empty());
+    }
+
+    #[test]
+    fn several_matches_complete_what_they_share_and_are_listed() {
+        let mut console = Console {
+            input: "se".to_string(),
+            ..default()
+        };
+        console.complete();
+        assert_eq!(console.input, "se");
+        assert_eq!(console.lines.back().unwrap(), "set_speed  seed");
+
+        console.input = "set".to_string();
+        console.complete();
+        assert_eq!(console.input, "set_speed ");
+
+        console.input = "g".to_string();
+        console.complete();
+        assert_eq!(console.input, "g");
+        assert_eq!(console.lines.back().unwrap(), "god  give");
+    }
+
+    #[test]
+    fn only_the_command_name_is_completed() {
+        let mut console = Console {
+            input: "give pow".to_string(),
+            ..default()
+        };
+        console.complete();
+        assert_eq!(console.input, "give pow");
+        assert!(console.lines.is_empty());
+    }
+}
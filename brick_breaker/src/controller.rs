@@ -0,0 +1,290 @@
+use bevy::{input::gamepad::GamepadAxisType, prelude::*};
+
+use crate::components::{Ball, GameRng, GameState, Paddle, Velocity};
+use crate::playfield::Playfield;
+use crate::{launch_ball, BALL_RADIUS, PADDLE_SPEED, PADDLE_WIDTH};
+
+// How far off the ball the autopilot is happy to leave the paddle
+const AUTOPILOT_DEADZONE: f32 = PADDLE_WIDTH / 4.;
+
+pub use common::controller::{ControllerInput, PaddleController};
+
+// What a controller wants the paddle to do each tick
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct PaddleCommand {
+    // From -1 (full speed left) to 1 (full speed right)
+    pub steer: f32,
+    // Send the ball off, if it's still sitting on the paddle
+    pub launch: bool,
+    // Move straight to this x this tick instead of steering, as far as the walls allow
+    pub follow: Option<f32>,
+}
+
+// What a controller gets to see of the game when it decides how to move
+#[derive(Debug, Clone)]
+pub struct PaddleView {
+    pub paddle: Vec3,
+    // Position and velocity of every ball in play
+    pub balls: Vec<(Vec3, Vec3)>,
+    // The ball is sitting on the paddle waiting to be launched
+    pub serving: bool,
+    pub playfield: Playfield,
+}
+
+// The latest command for the paddle, carried out every tick until the next one comes in. A launch
+// is kept until a tick takes it up, so it isn't lost on a frame that doesn't run one
+#[derive(Component, Debug, Default)]
+pub struct Steering(pub PaddleCommand);
+
+pub type Controller = common::controller::Controller<PaddleView, PaddleCommand>;
+// Steered from outside the game by a bot on the network
+pub type External = common::controller::External<PaddleView, PaddleCommand>;
+
+// Who plays the paddle, handed over when it's spawned
+#[derive(Resource)]
+pub struct ControllerSetup {
+    pub paddle: Option<Controller>,
+}
+
+impl ControllerSetup {
+    pub fn new(paddle: impl PaddleController<View = PaddleView, Command = PaddleCommand>) -> Self {
+        Self {
+            paddle: Some(Controller::new(paddle)),
+        }
+    }
+}
+
+impl Default for ControllerSetup {
+    fn default() -> Self {
+        Self::new(Keyboard)
+    }
+}
+
+// A/D to move, space to launch
+pub struct Keyboard;
+
+impl PaddleController for Keyboard {
+    type View = PaddleView;
+    type Command = PaddleCommand;
+
+    fn steer(&mut self, _view: &PaddleView, input: &ControllerInput) -> PaddleCommand {
+        let steer = if input.keys.pressed(KeyCode::KeyA) {
+            -1.
+        } else if input.keys.pressed(KeyCode::KeyD) {
+            1.
+        } else {
+            0.
+        };
+        PaddleCommand {
+            steer,
+            launch: input.keys.just_pressed(KeyCode::Space),
+            follow: None,
+        }
+    }
+}
+
+// Follows the mouse across the field, with a left click to launch
+pub struct Mouse;
+
+impl PaddleController for Mouse {
+    type View = PaddleView;
+    type Command = PaddleCommand;
+
+    fn steer(&mut self, _view: &PaddleView, input: &ControllerInput) -> PaddleCommand {
+        PaddleCommand {
+            steer: 0.,
+            launch: input.mouse_buttons.just_pressed(MouseButton::Left),
+            follow: input.cursor().map(|cursor| cursor.x),
+        }
+    }
+}
+
+// The left stick or d-pad of a gamepad, by the order they were connected in (0 is the
+// first), with the bottom face button to launch
+pub struct GamepadStick(pub usize);
+
+impl PaddleController for GamepadStick {
+    type View = PaddleView;
+    type Command = PaddleCommand;
+
+    fn steer(&mut self, _view: &PaddleView, input: &ControllerInput) -> PaddleCommand {
+        let Some(gamepad) = input.gamepads.iter().nth(self.0) else {
+            return PaddleCommand::default();
+        };
+
+        let button = |button| GamepadButton::new(gamepad, button);
+        let steer = if input.gamepad_buttons.pressed(button(GamepadButtonType::DPadLeft)) {
+            -1.
+        } else if input.gamepad_buttons.pressed(button(GamepadButtonType::DPadRight)) {
+            1.
+        } else {
+            input
+                .gamepad_axes
+                .get(GamepadAxis::new(gamepad, GamepadAxisType::LeftStickX))
+                .unwrap_or(0.)
+        };
+        PaddleCommand {
+            steer,
+            launch: input.gamepad_buttons.just_pressed(button(GamepadButtonType::South)),
+            follow: None,
+        }
+    }
+}
+
+// The built-in player: launches straight away, then works out where the lowest falling
+// ball will come down (bouncing off the side walls on the way) and waits there
+pub struct Autopilot;
+
+impl PaddleController for Autopilot {
+    type View = PaddleView;
+    type Command = PaddleCommand;
+
+    fn steer(&mut self, view: &PaddleView, _input: &ControllerInput) -> PaddleCommand {
+        let falling = view
+            .balls
+            .iter()
+            .filter(|(_, velocity)| velocity.y < 0.)
+            .min_by(|(a, _), (b, _)| a.y.total_cmp(&b.y));
+        let target = match falling {
+            Some(&(position, velocity)) => landing_x(position, velocity, view.paddle.y, &view.playfield),
+            // Nothing's coming down yet, so stay under the ball
+            None => view.balls.first().map_or(0., |(position, _)| position.x),
+        };
+
+        let gap = target - view.paddle.x;
+        PaddleCommand {
+            steer: if gap.abs() < AUTOPILOT_DEADZONE { 0. } else { gap.signum() },
+            launch: view.serving,
+            follow: None,
+        }
+    }
+}
+
+// Where a ball will be across the field when it gets down to `y`, folding in bounces off
+// the side walls
+fn landing_x(position: Vec3, velocity: Vec3, y: f32, playfield: &Playfield) -> f32 {
+    let time = (y - position.y) / velocity.y;
+    let left = playfield.left() + BALL_RADIUS;
+    let span = playfield.right() - BALL_RADIUS - left;
+
+    let unfolded = (position.x + velocity.x * time - left).rem_euclid(span * 2.);
+    if unfolded > span {
+        left + span * 2. - unfolded
+    } else {
+        left + unfolded
+    }
+}
+
+// Asks the paddle's controller what to do, every frame so no input is missed
+pub fn drive_paddle(
+    input: ControllerInput,
+    mut paddle_query: Query<(&Transform, &mut Controller, &mut Steering), With<Paddle>>,
+    ball_query: Query<(&Velocity, &Transform), With<Ball>>,
+    playfield: Res<Playfield>,
+    state: Res<State<GameState>>,
+) {
+    let balls: Vec<(Vec3, Vec3)> = ball_query
+        .iter()
+        .map(|(velocity, transform)| (transform.translation, velocity.0))
+        .collect();
+    let serving = state.get() == &GameState::Start;
+
+    for (transform, mut controller, mut steering) in paddle_query.iter_mut() {
+        let view = PaddleView {
+            paddle: transform.translation,
+            balls: balls.clone(),
+            serving,
+            playfield: *playfield,
+        };
+        let command = controller.0.steer(&view, &input);
+        steering.0 = PaddleCommand {
+            launch: steering.0.launch || command.launch,
+            ..command
+        };
+    }
+}
+
+// Carries out the paddle's latest command for this tick
+pub fn steer_paddle(
+    mut paddle_query: Query<(&mut Velocity, &mut Transform, &mut Steering), With<Paddle>>,
+    mut ball_query: Query<&mut Velocity, (With<Ball>, Without<Paddle>)>,
+    state: Res<State<GameState>>,
+    mut next_state: ResMut<NextState<GameState>>,
+    mut rng: ResMut<GameRng>,
+) {
+    let serving = state.get() == &GameState::Start;
+
+    for (mut velocity, mut transform, mut steering) in paddle_query.iter_mut() {
+        let command = steering.0;
+        steering.0.launch = false;
+        match command.follow {
+            // Put straight there rather than sent flying at it, and `clamp_paddle` stops it at the walls
+            Some(x) => {
+                transform.translation.x = x;
+                velocity.0.x = 0.;
+            }
+            None => velocity.0.x = command.steer.clamp(-1., 1.) * PADDLE_SPEED,
+        }
+
+        if command.launch && serving {
+            for mut ball_velocity in ball_query.iter_mut() {
+                if ball_velocity.0 == Vec3::ZERO {
+                    launch_ball(&mut ball_velocity, &mut rng);
+                }
+            }
+            next_state.set(GameState::InGame);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::components::Screen;
+    use crate::{headless_app, BrickBreakerPlugin};
+
+    // Always wants the paddle at the same x
+    struct FollowTo(f32);
+
+    impl PaddleController for FollowTo {
+        type View = PaddleView;
+        type Command = PaddleCommand;
+
+        fn steer(&mut self, _view: &PaddleView, _input: &ControllerInput) -> PaddleCommand {
+            PaddleCommand {
+                follow: Some(self.0),
+                ..default()
+            }
+        }
+    }
+
+    fn paddle_after_following(x: f32) -> (Vec3, Vec3) {
+        let mut app = headless_app(BrickBreakerPlugin {
+            seed: Some(1),
+            level: None,
+        });
+        app.insert_resource(ControllerSetup::new(FollowTo(x)));
+        app.world_mut().resource_mut::<NextState<Screen>>().set(Screen::Playing);
+        // The first frame puts the game on the field, and the next one runs the first tick
+        app.update();
+        app.update();
+        let mut paddles = app.world_mut().query_filtered::<(&Transform, &Velocity), With<Paddle>>();
+        let (transform, velocity) = paddles.single(app.world());
+        (transform.translation, velocity.0)
+    }
+
+    #[test]
+    fn following_puts_the_paddle_there_without_sending_it_flying() {
+        let (position, velocity) = paddle_after_following(100.);
+        assert_eq!(position.x, 100.);
+        assert_eq!(velocity, Vec3::ZERO);
+    }
+
+    #[test]
+    fn following_stops_at_the_walls() {
+        let playfield = Playfield::default();
+        let (position, velocity) = paddle_after_following(10_000.);
+        assert!(position.x + PADDLE_WIDTH / 2. <= playfield.right());
+        assert_eq!(velocity, Vec3::ZERO);
+    }
+}
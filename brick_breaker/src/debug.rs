@@ -0,0 +1,47 @@
+use std::marker::PhantomData;
+
+use bevy::prelude::*;
+use common::debug::{DebugInfo, DebugLines, DebugOverlay, COLLIDER_COLOR, PADDLE_COLOR};
+
+use crate::components::{Ball, Collider, GameState, Paddle, Screen};
+use crate::BALL_RADIUS;
+
+// The debug overlay, with the box of every collider and the state of the game
+pub struct DebugOverlayPlugin;
+
+impl Plugin for DebugOverlayPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins(common::debug::DebugOverlayPlugin::<Ball> {
+            ball_radius: BALL_RADIUS,
+            text_position: Style {
+                top: Val::Px(10.0),
+                right: Val::Px(10.0),
+                ..default()
+            },
+            balls: PhantomData,
+        })
+        .add_systems(
+            Update,
+            (draw_colliders, show_game_state.in_set(DebugInfo)).run_if(resource_equals(DebugOverlay(true))),
+        );
+    }
+}
+
+fn draw_colliders(mut gizmos: Gizmos, colliders: Query<(&Transform, Has<Paddle>), With<Collider>>) {
+    // Colliders are sized by their scale, and the bumpers are turned
+    for (transform, is_paddle) in colliders.iter() {
+        let (angle, _, _) = transform.rotation.to_euler(EulerRot::ZYX);
+        let color = if is_paddle { PADDLE_COLOR } else { COLLIDER_COLOR };
+        gizmos.rect_2d(transform.translation.truncate(), angle, transform.scale.truncate(), color);
+    }
+}
+
+fn show_game_state(
+    screen: Res<State<Screen>>,
+    state: Option<Res<State<GameState>>>,
+    mut lines: ResMut<DebugLines>,
+) {
+    let state = state.map_or("-".to_string(), |state| format!("{:?}", state.get()));
+    lines.0.push(format!("Screen: {:?}", screen.get()));
+    lines.0.push(format!("Game state: {}", state));
+}
@@ -0,0 +1,439 @@
+use std::mem;
+use std::path::PathBuf;
+
+use bevy::{
+    ecs::system::SystemParam,
+    input::mouse::MouseWheel,
+    prelude::*,
+    sprite::{MaterialMesh2dBundle, Mesh2dHandle},
+    window::PrimaryWindow,
+};
+
+use crate::assets::GameAssets;
+use crate::components::{BrickKind, GridCell, Screen};
+use crate::controller::ControllerSetup;
+use crate::events::GameFinished;
+use crate::level::{Level, LevelGrid};
+use crate::playfield::{cursor_position, Playfield};
+use crate::{spawn_level, BRICK_HEIGHT, BRICK_WIDTH};
+
+// Most edits that can be undone
+const UNDO_LIMIT: usize = 200;
+
+const CONTROLS: &str = "Left click: place brick / change kind   Right click: erase   Wheel: brick kind\n\
+    Ctrl+Z: undo   Ctrl+Y: redo   Ctrl+S: save   Ctrl+O: reload   Enter: play-test   Esc: title";
+
+// Edits a level file with the mouse, with bricks snapping to the grid the game lays levels
+// out on. The game starts in the editor, and Enter plays the level as it stands until the
+// game is finished or quit from the pause menu.
+pub struct EditorPlugin {
+    // The level file to edit, it's created on the first save if it doesn't exist yet
+    pub path: PathBuf,
+}
+
+impl Plugin for EditorPlugin {
+    fn build(&self, app: &mut App) {
+        let level = if self.path.exists() {
+            let level = Level::load(&self.path).unwrap_or_else(|error| panic!("{}", error));
+            if let Err(error) = level.validate(&LevelGrid::new(&Playfield::default())) {
+                panic!("{} can't be edited: {}", self.path.display(), error);
+            }
+            level
+        } else {
+            let name = self
+                .path
+                .file_stem()
+                .map_or("Untitled".to_string(), |stem| stem.to_string_lossy().into_owned());
+            Level::empty(&name)
+        };
+
+        app.insert_resource(Editor {
+            path: self.path.clone(),
+            level,
+            kind: BrickKind::Standard,
+            undo: Vec::new(),
+            redo: Vec::new(),
+            stroke_saved: false,
+            benched: None,
+            message: String::new(),
+        })
+        .insert_state(Screen::Editor)
+        .add_systems(OnEnter(Screen::Editor), (spawn_editor_text, show_bricks))
+        .add_systems(
+            Update,
+            (
+                (edit_with_mouse, choose_kind, edit_with_keys),
+                (show_bricks, show_status).run_if(resource_changed::<Editor>),
+                draw_grid,
+            )
+                .chain()
+                .run_if(in_state(Screen::Editor)),
+        )
+        .add_systems(OnEnter(Screen::PlayTest), (start_play_test.before(spawn_level), spawn_level))
+        .add_systems(OnExit(Screen::PlayTest), end_play_test)
+        .add_systems(
+            Update,
+            stop_play_test
+                .run_if(on_event::<GameFinished>())
+                .run_if(in_state(Screen::PlayTest)),
+        );
+    }
+}
+
+#[derive(Resource)]
+struct Editor {
+    path: PathBuf,
+    level: Level,
+    // The kind of brick the mouse places
+    kind: BrickKind,
+    undo: Vec<Level>,
+    redo: Vec<Level>,
+    // Whether the current mouse stroke has saved an undo step yet, so a whole drag undoes at once
+    stroke_saved: bool,
+    // The level the game was playing before a play-test, to put back afterwards
+    benched: Option<Level>,
+    // What happened last, shown under the controls
+    message: String,
+}
+
+impl Editor {
+    fn set(&mut self, cell: GridCell, kind: Option<BrickKind>) {
+        if !self.stroke_saved {
+            self.save_undo();
+            self.stroke_saved = true;
+        }
+        self.level.set(cell, kind);
+    }
+
+    fn replace(&mut self, level: Level) {
+        self.save_undo();
+        self.level = level;
+    }
+
+    fn save_undo(&mut self) {
+        self.undo.push(self.level.clone());
+        if self.undo.len() > UNDO_LIMIT {
+            self.undo.remove(0);
+        }
+        self.redo.clear();
+    }
+
+    fn undo(&mut self) -> bool {
+        let Some(level) = self.undo.pop() else {
+            return false;
+        };
+        self.redo.push(mem::replace(&mut self.level, level));
+        true
+    }
+
+    fn redo(&mut self) -> bool {
+        let Some(level) = self.redo.pop() else {
+            return false;
+        };
+        self.undo.push(mem::replace(&mut self.level, level));
+        true
+    }
+}
+
+#[derive(Component)]
+struct EditorBrick;
+
+#[derive(Component)]
+struct EditorStatus;
+
+// The grid cell under the mouse
+#[derive(SystemParam)]
+struct Cursor<'w, 's> {
+    windows: Query<'w, 's, &'static Window, With<PrimaryWindow>>,
+    cameras: Query<'w, 's, (&'static Camera, &'static GlobalTransform)>,
+    playfield: Res<'w, Playfield>,
+}
+
+impl Cursor<'_, '_> {
+    fn cell(&self) -> Option<GridCell> {
+        let (camera, transform) = self.cameras.get_single().ok()?;
+        let position = cursor_position(self.windows.get_single().ok()?, camera, transform)?;
+        LevelGrid::new(&self.playfield).cell_at(position)
+    }
+}
+
+fn spawn_editor_text(mut commands: Commands) {
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    // Bottom right, out of the way of the time scale in the bottom left
+                    position_type: PositionType::Absolute,
+                    bottom: Val::Px(10.0),
+                    right: Val::Px(10.0),
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::FlexEnd,
+                    row_gap: Val::Px(10.0),
+                    ..default()
+                },
+                ..default()
+            },
+            StateScoped(Screen::Editor),
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                TextBundle::from_section(
+                    "",
+                    TextStyle {
+                        font_size: 24.0,
+                        ..default()
+                    },
+                ),
+                EditorStatus,
+            ));
+            parent.spawn(TextBundle::from_section(
+                CONTROLS,
+                TextStyle {
+                    font_size: 18.0,
+                    color: Color::srgb(0.6, 0.6, 0.6),
+                    ..default()
+                },
+            ));
+        });
+}
+
+fn edit_with_mouse(cursor: Cursor, mouse: Res<ButtonInput<MouseButton>>, mut editor: ResMut<Editor>) {
+    if mouse.any_just_pressed([MouseButton::Left, MouseButton::Right]) {
+        editor.bypass_change_detection().stroke_saved = false;
+    }
+    let Some(cell) = cursor.cell() else {
+        return;
+    };
+
+    let current = editor.level.kind_at(cell);
+    let kind = if mouse.pressed(MouseButton::Right) {
+        None
+    } else if mouse.just_pressed(MouseButton::Left) {
+        // Clicking a brick changes its kind, clicking an empty cell fills it
+        Some(current.map_or(editor.kind, |kind| kind.next()))
+    } else if mouse.pressed(MouseButton::Left) && current.is_none() {
+        // Dragging only fills empty cells
+        Some(editor.kind)
+    } else {
+        return;
+    };
+
+    if kind != current {
+        if let Some(kind) = kind {
+            editor.kind = kind;
+        }
+        editor.set(cell, kind);
+    }
+}
+
+fn choose_kind(mut wheel: EventReader<MouseWheel>, mut editor: ResMut<Editor>) {
+    for event in wheel.read() {
+        if event.y > 0. {
+            editor.kind = editor.kind.previous();
+        } else if event.y < 0. {
+            editor.kind = editor.kind.next();
+        }
+    }
+}
+
+fn edit_with_keys(
+    keys: Res<ButtonInput<KeyCode>>,
+    playfield: Res<Playfield>,
+    mut editor: ResMut<Editor>,
+    mut next_screen: ResMut<NextState<Screen>>,
+) {
+    let ctrl = keys.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight]);
+    let shift = keys.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]);
+
+    if ctrl && (keys.just_pressed(KeyCode::KeyY) || shift && keys.just_pressed(KeyCode::KeyZ)) {
+        editor.message = if editor.redo() { "Redone" } else { "Nothing to redo" }.to_string();
+    } else if ctrl && keys.just_pressed(KeyCode::KeyZ) {
+        editor.message = if editor.undo() { "Undone" } else { "Nothing to undo" }.to_string();
+    } else if ctrl && keys.just_pressed(KeyCode::KeyS) {
+        editor.message = match editor.level.save(&editor.path) {
+            Ok(()) => format!("Saved {}", editor.path.display()),
+            Err(error) => error,
+        };
+    } else if ctrl && keys.just_pressed(KeyCode::KeyO) {
+        let loaded = Level::load(&editor.path).and_then(|level| {
+            level
+                .validate(&LevelGrid::new(&playfield))
+                .map(|_| level)
+                .map_err(|error| format!("{} can't be edited: {}", editor.path.display(), error))
+        });
+        editor.message = match loaded {
+            Ok(level) => {
+                editor.replace(level);
+                format!("Loaded {}", editor.path.display())
+            }
+            Err(error) => error,
+        };
+    } else if keys.just_pressed(KeyCode::Enter) {
+        next_screen.set(Screen::PlayTest);
+    } else if keys.just_pressed(KeyCode::Escape) {
+        next_screen.set(Screen::Title);
+    }
+}
+
+// Rebuilds the bricks on screen from the level being edited
+fn show_bricks(
+    mut commands: Commands,
+    mut assets: ResMut<GameAssets>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    playfield: Res<Playfield>,
+    editor: Res<Editor>,
+    shown: Query<Entity, With<EditorBrick>>,
+) {
+    for entity in shown.iter() {
+        commands.entity(entity).despawn();
+    }
+
+    let grid = LevelGrid::new(&playfield);
+    for brick in &editor.level.bricks {
+        commands.spawn((
+            MaterialMesh2dBundle {
+                mesh: Mesh2dHandle(assets.square.clone()),
+                material: assets.material(&mut materials, brick.display_color()),
+                transform: Transform {
+                    translation: grid.cell_center(brick.cell()).extend(0.0),
+                    scale: Vec3::new(BRICK_WIDTH, BRICK_HEIGHT, 1.0),
+                    ..default()
+                },
+                ..default()
+            },
+            EditorBrick,
+            StateScoped(Screen::Editor),
+        ));
+    }
+}
+
+fn show_status(editor: Res<Editor>, mut query: Query<&mut Text, With<EditorStatus>>) {
+    for mut text in query.iter_mut() {
+        text.sections[0].value = format!(
+            "{} ({}): {} bricks, placing {:?}\n{}",
+            editor.level.name,
+            editor.path.display(),
+            editor.level.bricks.len(),
+            editor.kind,
+            editor.message
+        );
+    }
+}
+
+// Outlines every cell, and the one under the mouse in the color of the brick it would place
+fn draw_grid(mut gizmos: Gizmos, cursor: Cursor, editor: Res<Editor>) {
+    let grid = LevelGrid::new(&cursor.playfield);
+    let size = Vec2::new(BRICK_WIDTH, BRICK_HEIGHT);
+    for row in 0..grid.rows {
+        for column in 0..grid.columns {
+            let center = grid.cell_center(GridCell { column, row });
+            gizmos.rect_2d(center, 0., size, Color::srgb(0.2, 0.2, 0.2));
+        }
+    }
+
+    if let Some(cell) = cursor.cell() {
+        gizmos.rect_2d(grid.cell_center(cell), 0., size + 4., editor.kind.color());
+    }
+}
+
+// Plays the level as it is in the editor, with the keyboard
+fn start_play_test(mut commands: Commands, mut editor: ResMut<Editor>, mut level: ResMut<Level>) {
+    editor.benched = Some(mem::replace(&mut *level, editor.level.clone()));
+    commands.insert_resource(ControllerSetup::default());
+}
+
+fn end_play_test(mut editor: ResMut<Editor>, mut level: ResMut<Level>) {
+    if let Some(benched) = editor.benched.take() {
+        *level = benched;
+    }
+}
+
+fn stop_play_test(mut next_screen: ResMut<NextState<Screen>>) {
+    next_screen.set(Screen::Editor);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn editor() -> Editor {
+        Editor {
+            path: PathBuf::from("test.ron"),
+            level: Level::empty("Test"),
+            kind: BrickKind::Standard,
+            undo: Vec::new(),
+            redo: Vec::new(),
+            stroke_saved: false,
+            benched: None,
+            message: String::new(),
+        }
+    }
+
+    fn cell(column: u32) -> GridCell {
+        GridCell { column, row: 0 }
+    }
+
+    // A single click, its own stroke
+    fn click(editor: &mut Editor, column: u32) {
+        editor.stroke_saved = false;
+        editor.set(cell(column), Some(BrickKind::Standard));
+    }
+
+    #[test]
+    fn undo_and_redo_step_through_the_edits() {
+        let mut editor = editor();
+        click(&mut editor, 0);
+        click(&mut editor, 1);
+
+        assert!(editor.undo());
+        assert_eq!(editor.level.bricks.len(), 1);
+        assert!(editor.undo());
+        assert!(editor.level.bricks.is_empty());
+        assert!(!editor.undo());
+
+        assert!(editor.redo());
+        assert!(editor.redo());
+        assert_eq!(editor.level.bricks.len(), 2);
+        assert!(!editor.redo());
+    }
+
+    #[test]
+    fn a_whole_stroke_undoes_at_once() {
+        let mut editor = editor();
+        click(&mut editor, 0);
+        // Dragging across more cells without letting go
+        editor.set(cell(1), Some(BrickKind::Standard));
+        editor.set(cell(2), Some(BrickKind::Standard));
+        assert_eq!(editor.level.bricks.len(), 3);
+
+        assert!(editor.undo());
+        assert!(editor.level.bricks.is_empty());
+        assert!(!editor.undo());
+    }
+
+    #[test]
+    fn a_new_edit_clears_the_redo() {
+        let mut editor = editor();
+        click(&mut editor, 0);
+        click(&mut editor, 1);
+        editor.undo();
+        assert_eq!(editor.redo.len(), 1);
+
+        click(&mut editor, 2);
+        assert!(editor.redo.is_empty());
+        assert!(!editor.redo());
+    }
+
+    #[test]
+    fn only_the_latest_edits_can_be_undone() {
+        let mut editor = editor();
+        for column in 0..UNDO_LIMIT as u32 + 10 {
+            click(&mut editor, column);
+        }
+        assert_eq!(editor.undo.len(), UNDO_LIMIT);
+
+        while editor.undo() {}
+        // The first ten edits are past undoing
+        assert_eq!(editor.level.bricks.len(), 10);
+    }
+}
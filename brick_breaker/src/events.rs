@@ -0,0 +1,57 @@
+use bevy::prelude::*;
+
+use crate::components::BrickKind;
+
+// The ball bounced off the paddle. `offset` is where it struck, from -1.0 (left edge) to 1.0 (right edge)
+#[derive(Event)]
+pub struct BallHitPaddle {
+    pub ball: Entity,
+    pub paddle: Entity,
+    pub offset: f32,
+}
+
+// A brick was hit but still has health left
+#[derive(Event)]
+pub struct BrickDamaged {
+    pub brick: Entity,
+    pub position: Vec2,
+    pub kind: BrickKind,
+    pub color: Color,
+    pub remaining: u32,
+}
+
+// A brick was hit for the last time and has been despawned
+#[derive(Event)]
+pub struct BrickDestroyed {
+    pub brick: Entity,
+    pub position: Vec2,
+    pub kind: BrickKind,
+    pub color: Color,
+}
+
+// The ball bounced off a wall or obstacle at `point`. `normal` is the direction of the face it hit
+#[derive(Event)]
+pub struct WallBounce {
+    pub ball: Entity,
+    pub wall: Entity,
+    pub point: Vec2,
+    pub normal: Vec2,
+}
+
+// The ball went past the paddle and left the playfield
+#[derive(Event)]
+pub struct BallLost {
+    pub ball: Entity,
+    pub position: Vec2,
+}
+
+// Start the game over with a freshly built level
+#[derive(Event)]
+pub struct RestartGame;
+
+// Move on from a completed level to the next one, in the modes that have one
+#[derive(Event)]
+pub struct NextLevel;
+
+// The player is done with a game that's over, one way or the other
+pub use common::highscore::GameFinished;
@@ -0,0 +1,297 @@
+use bevy::prelude::*;
+
+use crate::components::{GameState, Score, Screen};
+use crate::events::{GameFinished, NextLevel, RestartGame};
+use crate::generator::Endless;
+
+// Seconds the demo stays on the level complete and game over screens
+const DEMO_OVERLAY_TIME: f32 = 3.;
+
+// Pausing, and what happens once a level is over:
+//
+//   Start, InGame  --P/Esc-->      Paused  --P/Esc-->  back where it was
+//   InGame         --last brick--> LevelComplete  --Enter-->  the next endless stage, or the end
+//   InGame         --last ball-->  GameOver       --Enter-->  the end
+//
+// The end is a `GameFinished` for the screen to act on. R starts the game over from any of
+// these, and Q in the pause menu quits to the title screen, or the editor when play-testing.
+// The demo can't be paused, and moves on from the end of a level by itself.
+pub struct GameFlowPlugin;
+
+impl Plugin for GameFlowPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(GameState::Paused), spawn_pause_menu)
+            .add_systems(OnEnter(GameState::LevelComplete), spawn_level_over)
+            .add_systems(OnEnter(GameState::GameOver), spawn_level_over)
+            .add_systems(
+                Update,
+                (
+                    pause
+                        .run_if(in_state(GameState::Start).or_else(in_state(GameState::InGame)))
+                        .run_if(in_state(Screen::Playing).or_else(in_state(Screen::PlayTest))),
+                    pause_menu.run_if(in_state(GameState::Paused)),
+                    level_over.run_if(in_state(GameState::LevelComplete).or_else(in_state(GameState::GameOver))),
+                ),
+            );
+    }
+}
+
+// The state to go back to when the game is unpaused
+#[derive(Resource)]
+struct Resume(GameState);
+
+#[derive(Resource)]
+struct DemoTimer(Timer);
+
+fn pause_pressed(keys: &ButtonInput<KeyCode>, gamepad_buttons: &ButtonInput<GamepadButton>) -> bool {
+    keys.any_just_pressed([KeyCode::KeyP, KeyCode::Escape])
+        || gamepad_buttons.get_just_pressed().any(|button| button.button_type == GamepadButtonType::Start)
+}
+
+fn pause(
+    mut commands: Commands,
+    keys: Res<ButtonInput<KeyCode>>,
+    gamepad_buttons: Res<ButtonInput<GamepadButton>>,
+    state: Res<State<GameState>>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    if pause_pressed(&keys, &gamepad_buttons) {
+        commands.insert_resource(Resume(*state.get()));
+        next_state.set(GameState::Paused);
+    }
+}
+
+fn pause_menu(
+    keys: Res<ButtonInput<KeyCode>>,
+    gamepad_buttons: Res<ButtonInput<GamepadButton>>,
+    resume: Res<Resume>,
+    screen: Res<State<Screen>>,
+    mut next_state: ResMut<NextState<GameState>>,
+    mut next_screen: ResMut<NextState<Screen>>,
+    mut restart: EventWriter<RestartGame>,
+) {
+    if pause_pressed(&keys, &gamepad_buttons) {
+        next_state.set(resume.0);
+    } else if keys.just_pressed(KeyCode::KeyR) {
+        restart.send(RestartGame);
+    } else if keys.just_pressed(KeyCode::KeyQ) {
+        next_screen.set(if *screen.get() == Screen::PlayTest { Screen::Editor } else { Screen::Title });
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn level_over(
+    keys: Res<ButtonInput<KeyCode>>,
+    mouse: Res<ButtonInput<MouseButton>>,
+    gamepad_buttons: Res<ButtonInput<GamepadButton>>,
+    time: Res<Time>,
+    timer: Option<ResMut<DemoTimer>>,
+    state: Res<State<GameState>>,
+    endless: Option<Res<Endless>>,
+    score: Res<Score>,
+    mut restart: EventWriter<RestartGame>,
+    mut next_level: EventWriter<NextLevel>,
+    mut finished: EventWriter<GameFinished>,
+) {
+    let demo = timer.is_some();
+    let carry_on = match timer {
+        Some(mut timer) => timer.0.tick(time.delta()).just_finished(),
+        None => {
+            if keys.just_pressed(KeyCode::KeyR) {
+                restart.send(RestartGame);
+                return;
+            }
+            keys.any_just_pressed([KeyCode::Enter, KeyCode::Space])
+                || mouse.just_pressed(MouseButton::Left)
+                || gamepad_buttons.get_just_pressed().any(|button| button.button_type == GamepadButtonType::South)
+        }
+    };
+    if !carry_on {
+        return;
+    }
+
+    // Only games started from the title screen go on to the next endless stage
+    if *state.get() == GameState::LevelComplete && endless.is_some() && !demo {
+        next_level.send(NextLevel);
+    } else {
+        finished.send(GameFinished { score: score.0 });
+    }
+}
+
+fn spawn_pause_menu(mut commands: Commands) {
+    commands
+        .spawn((overlay(), StateScoped(GameState::Paused)))
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section("PAUSED", text_style(80.0)));
+            parent.spawn(TextBundle::from_section(
+                "P / Esc: resume   R: restart   Q: quit",
+                text_style(24.0),
+            ));
+        });
+}
+
+fn spawn_level_over(
+    mut commands: Commands,
+    score: Res<Score>,
+    screen: Res<State<Screen>>,
+    state: Res<State<GameState>>,
+) {
+    let title = if *state.get() == GameState::GameOver { "GAME OVER" } else { "LEVEL COMPLETE" };
+    let demo = *screen.get() == Screen::Demo;
+    if demo {
+        commands.insert_resource(DemoTimer(Timer::from_seconds(DEMO_OVERLAY_TIME, TimerMode::Once)));
+    } else {
+        commands.remove_resource::<DemoTimer>();
+    }
+
+    commands
+        .spawn((overlay(), StateScoped(*state.get())))
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section(title, text_style(80.0)));
+            parent.spawn(TextBundle::from_section(format!("Score: {}", score.0), text_style(40.0)));
+            if !demo {
+                parent.spawn(TextBundle::from_section("Enter: continue   R: restart", text_style(24.0)));
+            }
+        });
+}
+
+fn overlay() -> NodeBundle {
+    NodeBundle {
+        style: Style {
+            width: Val::Percent(100.0),
+            height: Val::Percent(100.0),
+            position_type: PositionType::Absolute,
+            flex_direction: FlexDirection::Column,
+            align_items: AlignItems::Center,
+            justify_content: JustifyContent::Center,
+            row_gap: Val::Px(20.0),
+            ..default()
+        },
+        background_color: BackgroundColor(Color::BLACK.with_alpha(0.6)),
+        ..default()
+    }
+}
+
+fn text_style(font_size: f32) -> TextStyle {
+    TextStyle {
+        font_size,
+        ..default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::input::{
+        keyboard::{Key, KeyboardInput},
+        ButtonState,
+    };
+
+    use super::*;
+    use crate::components::{Ball, Brick, Paddle, Velocity};
+    use crate::generator::EndlessPlugin;
+    use crate::level::Level;
+    use crate::{headless_app, BrickBreakerPlugin, BALL_SPEED};
+
+    fn game(endless: bool) -> App {
+        let mut app = headless_app(BrickBreakerPlugin {
+            seed: Some(1),
+            level: None,
+        });
+        app.add_plugins(GameFlowPlugin);
+        if endless {
+            app.add_plugins(EndlessPlugin { seed: 1 });
+        }
+        app.world_mut().resource_mut::<NextState<Screen>>().set(Screen::Playing);
+        app.update();
+
+        // Launched straight up, so it's moving and never lost
+        let mut balls = app.world_mut().query_filtered::<&mut Velocity, With<Ball>>();
+        for mut velocity in balls.iter_mut(app.world_mut()) {
+            velocity.0 = Vec3::new(0., BALL_SPEED, 0.);
+        }
+        app.world_mut().resource_mut::<NextState<GameState>>().set(GameState::InGame);
+        app.update();
+        app
+    }
+
+    // Presses and lets go of a key, a frame each
+    fn press(app: &mut App, key_code: KeyCode, logical_key: Key) {
+        for state in [ButtonState::Pressed, ButtonState::Released] {
+            app.world_mut().send_event(KeyboardInput {
+                key_code,
+                logical_key: logical_key.clone(),
+                state,
+                window: Entity::PLACEHOLDER,
+            });
+            app.update();
+        }
+    }
+
+    fn state(app: &App) -> GameState {
+        *app.world().resource::<State<GameState>>().get()
+    }
+
+    fn count<T: Component>(app: &mut App) -> usize {
+        app.world_mut().query_filtered::<(), With<T>>().iter(app.world()).count()
+    }
+
+    fn ball_position(app: &mut App) -> Vec3 {
+        let mut balls = app.world_mut().query_filtered::<&Transform, With<Ball>>();
+        balls.single(app.world()).translation
+    }
+
+    #[test]
+    fn pausing_stops_play_until_unpaused() {
+        let mut app = game(false);
+        press(&mut app, KeyCode::KeyP, Key::Character("p".into()));
+        assert_eq!(state(&app), GameState::Paused);
+
+        let paused_at = ball_position(&mut app);
+        for _ in 0..10 {
+            app.update();
+        }
+        assert_eq!(ball_position(&mut app), paused_at);
+
+        press(&mut app, KeyCode::Escape, Key::Escape);
+        assert_eq!(state(&app), GameState::InGame);
+        app.update();
+        assert_ne!(ball_position(&mut app), paused_at);
+    }
+
+    #[test]
+    fn a_complete_level_carries_on_to_the_next_stage() {
+        let mut app = game(true);
+        let first = app.world().resource::<Level>().name.clone();
+        app.world_mut().resource_mut::<NextState<GameState>>().set(GameState::LevelComplete);
+        app.update();
+
+        press(&mut app, KeyCode::Enter, Key::Enter);
+        app.update();
+        assert_eq!(state(&app), GameState::Start);
+        assert_ne!(app.world().resource::<Level>().name, first);
+        assert!(app.world().resource::<Level>().name.ends_with("stage 2"));
+    }
+
+    #[test]
+    fn restarting_after_game_over_puts_the_whole_level_back_once() {
+        let mut app = game(false);
+        let bricks = app.world().resource::<Level>().bricks.len();
+        assert_eq!(count::<Brick>(&mut app), bricks);
+
+        // Knock a few bricks out first
+        let mut query = app.world_mut().query_filtered::<Entity, With<Brick>>();
+        let broken: Vec<Entity> = query.iter(app.world()).take(5).collect();
+        for brick in broken {
+            app.world_mut().despawn(brick);
+        }
+        app.world_mut().resource_mut::<NextState<GameState>>().set(GameState::GameOver);
+        app.update();
+
+        press(&mut app, KeyCode::KeyR, Key::Character("r".into()));
+        app.update();
+        assert_eq!(state(&app), GameState::Start);
+        assert_eq!(count::<Brick>(&mut app), bricks);
+        assert_eq!(count::<Paddle>(&mut app), 1);
+        assert_eq!(count::<Ball>(&mut app), 1);
+    }
+}
@@ -0,0 +1,309 @@
+use bevy::prelude::*;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+use crate::assets::GameAssets;
+use crate::components::{Ball, Brick, BrickKind, GameState, GridCell, Paddle, Screen, Velocity};
+use crate::events::{NextLevel, RestartGame};
+use crate::level::{Level, LevelGrid};
+use crate::playfield::Playfield;
+use crate::{return_ball, spawn_bricks, spawn_level};
+
+// How the generator lays out a level
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GeneratorParams {
+    // Chance of each cell getting a brick, from 0 to 1
+    pub density: f32,
+    // Fraction of the grid's rows, from the top, that can have bricks
+    pub depth: f32,
+    // Mirror the left half of the layout onto the right
+    pub mirror: bool,
+    // Chance of a brick being strong or indestructible rather than standard
+    pub strong: f32,
+    pub indestructible: f32,
+}
+
+impl GeneratorParams {
+    // The difficulty curve of endless mode: the first stage is a few sparse rows of standard
+    // bricks, and each one after fills in more of the grid with tougher bricks
+    pub fn for_stage(stage: u32) -> Self {
+        let difficulty = 1. - 0.85_f32.powi(stage as i32);
+        Self {
+            density: 0.5 + 0.4 * difficulty,
+            depth: 0.4 + 0.6 * difficulty,
+            mirror: true,
+            strong: 0.05 + 0.4 * difficulty,
+            indestructible: 0.12 * difficulty,
+        }
+    }
+}
+
+impl Default for GeneratorParams {
+    fn default() -> Self {
+        Self::for_stage(0)
+    }
+}
+
+// Generates a layout from a seed. The same seed and parameters always give the same level,
+// so seeds can be shared.
+//
+// Indestructible bricks are never placed next to each other, even diagonally, so they can't
+// wall off any breakable bricks and every generated level can be cleared.
+pub fn generate(seed: u64, params: &GeneratorParams, grid: &LevelGrid) -> Level {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut level = Level::empty(&format!("Seed {}", seed));
+
+    let rows = ((grid.rows as f32 * params.depth).round() as u32).clamp(1, grid.rows);
+    let columns = if params.mirror { grid.columns.div_ceil(2) } else { grid.columns };
+    let density = params.density.clamp(0., 1.) as f64;
+
+    for row in 0..rows {
+        for column in 0..columns {
+            if !rng.gen_bool(density) {
+                continue;
+            }
+            let cells = mirrored(GridCell { column, row }, params.mirror, grid);
+
+            let roll: f32 = rng.gen();
+            let mut kind = if roll < params.indestructible {
+                BrickKind::Indestructible
+            } else if roll < params.indestructible + params.strong {
+                BrickKind::Strong
+            } else {
+                BrickKind::Standard
+            };
+            if kind == BrickKind::Indestructible && !room_for_indestructible(&level, &cells) {
+                kind = BrickKind::Strong;
+            }
+
+            for cell in cells {
+                level.set(cell, Some(kind));
+            }
+        }
+    }
+
+    // There's always something to break
+    if level.breakable().next().is_none() {
+        let column = rng.gen_range(0..columns);
+        let cell = GridCell { column, row: rows - 1 };
+        for cell in mirrored(cell, params.mirror, grid) {
+            level.set(cell, Some(BrickKind::Standard));
+        }
+    }
+
+    level
+}
+
+// The seed the given stage of an endless game started from `seed` is generated from, mixed so
+// neighbouring seeds don't share stages
+pub fn stage_seed(seed: u64, stage: u32) -> u64 {
+    seed ^ (stage as u64 + 1).wrapping_mul(0x9e37_79b9_7f4a_7c15)
+}
+
+// The given stage of an endless game started from `seed`
+pub fn endless_stage(seed: u64, stage: u32, grid: &LevelGrid) -> Level {
+    let mut level = generate(stage_seed(seed, stage), &GeneratorParams::for_stage(stage), grid);
+    level.name = format!("Endless {} stage {}", seed, stage + 1);
+    level
+}
+
+// The cell and its mirror image, if it has one
+fn mirrored(cell: GridCell, mirror: bool, grid: &LevelGrid) -> Vec<GridCell> {
+    let opposite = GridCell {
+        column: grid.columns - 1 - cell.column,
+        row: cell.row,
+    };
+    if mirror && opposite != cell {
+        vec![cell, opposite]
+    } else {
+        vec![cell]
+    }
+}
+
+fn room_for_indestructible(level: &Level, cells: &[GridCell]) -> bool {
+    let near = |a: GridCell, b: GridCell| a.column.abs_diff(b.column) <= 1 && a.row.abs_diff(b.row) <= 1;
+    let touching_each_other = cells.iter().any(|&a| cells.iter().any(|&b| a != b && near(a, b)));
+    let touching_others = level
+        .bricks
+        .iter()
+        .filter(|brick| brick.kind == BrickKind::Indestructible)
+        .any(|brick| cells.iter().any(|&cell| near(brick.cell(), cell)));
+    !touching_each_other && !touching_others
+}
+
+// Endless mode: carrying on from a cleared level moves on to a freshly generated one that's
+// a little harder. Every game started from the title screen, or restarted, starts over from
+// the first stage of the same seed.
+pub struct EndlessPlugin {
+    pub seed: u64,
+}
+
+impl Plugin for EndlessPlugin {
+    fn build(&self, app: &mut App) {
+        let grid = LevelGrid::new(&Playfield::default());
+        info!("Endless mode, seed {}", self.seed);
+
+        app.insert_resource(Endless {
+            seed: self.seed,
+            stage: 0,
+        })
+        .insert_resource(endless_stage(self.seed, 0, &grid))
+        .add_systems(
+            OnEnter(Screen::Playing),
+            (restart_endless.before(spawn_level), spawn_stage_text),
+        )
+        .add_systems(
+            Update,
+            (
+                restart_endless.before(spawn_level).run_if(on_event::<RestartGame>()),
+                next_stage.run_if(on_event::<NextLevel>()),
+                show_stage.run_if(resource_changed::<Endless>),
+            )
+                .chain()
+                .run_if(in_state(Screen::Playing)),
+        );
+    }
+}
+
+#[derive(Resource)]
+pub struct Endless {
+    seed: u64,
+    stage: u32,
+}
+
+impl Endless {
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    // Skips straight to a stage, counting from 0, carrying on from there
+    pub fn go_to_stage(&mut self, stage: u32, grid: &LevelGrid) -> Level {
+        self.stage = stage;
+        endless_stage(self.seed, stage, grid)
+    }
+}
+
+#[derive(Component)]
+struct StageText;
+
+fn restart_endless(mut endless: ResMut<Endless>, mut level: ResMut<Level>, playfield: Res<Playfield>) {
+    endless.stage = 0;
+    *level = endless_stage(endless.seed, 0, &LevelGrid::new(&playfield));
+}
+
+fn spawn_stage_text(mut commands: Commands) {
+    commands.spawn((
+        TextBundle::from_section(
+            "",
+            TextStyle {
+                font_size: 24.0,
+                ..default()
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            bottom: Val::Px(10.0),
+            right: Val::Px(10.0),
+            ..default()
+        }),
+        StageText,
+        StateScoped(Screen::Playing),
+    ));
+}
+
+#[allow(clippy::too_many_arguments)]
+fn next_stage(
+    mut commands: Commands,
+    mut assets: ResMut<GameAssets>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    playfield: Res<Playfield>,
+    mut endless: ResMut<Endless>,
+    mut level: ResMut<Level>,
+    leftover: Query<Entity, With<Brick>>,
+    mut ball_query: Query<(&mut Velocity, &mut Transform), With<Ball>>,
+    paddle_query: Query<&Transform, (With<Paddle>, Without<Ball>)>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    // Indestructible bricks are all that's left of the last stage
+    for entity in leftover.iter() {
+        commands.entity(entity).despawn();
+    }
+
+    endless.stage += 1;
+    *level = endless_stage(endless.seed, endless.stage, &LevelGrid::new(&playfield));
+    info!("Endless stage {}", endless.stage + 1);
+    spawn_bricks(&mut commands, &mut assets, &mut materials, &playfield, &level, Screen::Playing);
+
+    let paddle_transform = paddle_query.single();
+    for (mut velocity, mut transform) in ball_query.iter_mut() {
+        return_ball(&mut velocity, &mut transform, paddle_transform, &mut next_state);
+    }
+}
+
+fn show_stage(endless: Res<Endless>, mut query: Query<&mut Text, With<StageText>>) {
+    for mut text in query.iter_mut() {
+        text.sections[0].value = format!("Stage {}   Seed {}", endless.stage + 1, endless.seed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_same_seed_gives_the_same_layout() {
+        let grid = LevelGrid::new(&Playfield::default());
+        for stage in [0, 5] {
+            let params = GeneratorParams::for_stage(stage);
+            assert_eq!(generate(1234, &params, &grid), generate(1234, &params, &grid));
+            assert_eq!(endless_stage(1234, stage, &grid), endless_stage(1234, stage, &grid));
+        }
+    }
+
+    #[test]
+    fn different_seeds_give_different_layouts() {
+        let grid = LevelGrid::new(&Playfield::default());
+        let params = GeneratorParams::default();
+        assert_ne!(generate(1, &params, &grid).bricks, generate(2, &params, &grid).bricks);
+        assert_ne!(endless_stage(1, 0, &grid).bricks, endless_stage(2, 0, &grid).bricks);
+    }
+
+    #[test]
+    fn indestructible_bricks_never_touch() {
+        let grid = LevelGrid::new(&Playfield::default());
+        let crowded = GeneratorParams {
+            density: 1.,
+            depth: 1.,
+            indestructible: 0.8,
+            ..default()
+        };
+        for seed in 0..100 {
+            for params in [GeneratorParams::for_stage(seed as u32 % 30), crowded] {
+                for mirror in [true, false] {
+                    let level = generate(seed, &GeneratorParams { mirror, ..params }, &grid);
+                    let indestructible: Vec<GridCell> = level
+                        .bricks
+                        .iter()
+                        .filter(|brick| brick.kind == BrickKind::Indestructible)
+                        .map(|brick| brick.cell())
+                        .collect();
+                    for &a in &indestructible {
+                        for &b in &indestructible {
+                            let touching = a != b && a.column.abs_diff(b.column) <= 1 && a.row.abs_diff(b.row) <= 1;
+                            assert!(!touching, "seed {}: {:?} and {:?} touch", seed, a, b);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn generated_levels_fit_the_grid_with_something_to_break() {
+        let grid = LevelGrid::new(&Playfield::default());
+        for seed in 0..50 {
+            let level = generate(seed, &GeneratorParams::for_stage(seed as u32), &grid);
+            assert_eq!(level.validate(&grid), Ok(()));
+            assert!(level.breakable().next().is_some());
+        }
+    }
+}
@@ -0,0 +1,95 @@
+use std::marker::PhantomData;
+use std::path::PathBuf;
+
+use bevy::prelude::*;
+
+use crate::components::Screen;
+
+pub use common::highscore::HighScores;
+
+// Once a game started from the title screen is finished, offers a place in the mode's
+// high-score table if the score is good enough, and shows the table. It can also be shown
+// from the title screen.
+pub struct HighScorePlugin {
+    // Where to save the tables, `None` to keep them only until the game exits
+    pub path: Option<PathBuf>,
+    // The table to use
+    pub mode: String,
+    // Whether the player's games are recorded at all
+    pub record: bool,
+}
+
+impl Plugin for HighScorePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins(common::highscore::HighScorePlugin::<Screen> {
+            path: self.path.clone(),
+            mode: self.mode.clone(),
+            record: self.record,
+            screens: PhantomData,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use bevy::input::{
+        keyboard::{Key, KeyboardInput},
+        ButtonState,
+    };
+    use bevy::time::TimeUpdateStrategy;
+
+    use super::*;
+    use crate::components::{GameState, Score};
+    use crate::flow::GameFlowPlugin;
+    use crate::{headless_app, BrickBreakerPlugin};
+
+    fn press(app: &mut App, key_code: KeyCode, logical_key: Key) {
+        app.world_mut().send_event(KeyboardInput {
+            key_code,
+            logical_key,
+            state: ButtonState::Pressed,
+            window: Entity::PLACEHOLDER,
+        });
+    }
+
+    #[test]
+    fn the_enter_that_ends_the_game_doesnt_enter_a_name() {
+        let mut app = headless_app(BrickBreakerPlugin {
+            seed: Some(1),
+            level: None,
+        });
+        app.add_plugins((
+            GameFlowPlugin,
+            HighScorePlugin {
+                path: None,
+                mode: "test".to_string(),
+                record: true,
+            },
+        ));
+        // Events are only cleared out after a fixed update, so at a high frame rate they're
+        // still there a few frames later
+        app.insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_millis(2)));
+        app.world_mut().resource_mut::<NextState<Screen>>().set(Screen::Playing);
+        app.update();
+        app.world_mut().resource_mut::<Score>().0 = 100;
+        app.world_mut().resource_mut::<NextState<GameState>>().set(GameState::GameOver);
+        app.update();
+
+        press(&mut app, KeyCode::Enter, Key::Enter);
+        for _ in 0..10 {
+            app.update();
+        }
+        assert_eq!(*app.world().resource::<State<Screen>>().get(), Screen::NameEntry);
+        assert!(app.world().resource::<HighScores>().table("test").is_empty());
+
+        // A fresh Enter does finish
+        press(&mut app, KeyCode::Enter, Key::Enter);
+        for _ in 0..2 {
+            app.update();
+        }
+        assert_eq!(*app.world().resource::<State<Screen>>().get(), Screen::Leaderboard);
+        assert_eq!(app.world().resource::<HighScores>().table("test").len(), 1);
+    }
+}
@@ -0,0 +1,145 @@
+use std::fs;
+use std::path::Path;
+
+use image::RgbaImage;
+
+use crate::components::{BrickKind, GridCell};
+use crate::level::{Level, LevelBrick, LevelGrid};
+
+// Pixels less opaque than this are left empty
+const OPAQUE_ALPHA: u8 = 128;
+
+// Turns a PNG or ASCII art file into a level, picking by the file extension. The art is
+// centered across the top of the grid, one brick per pixel or character.
+pub fn import(path: &Path, name: &str, grid: &LevelGrid) -> Result<Level, String> {
+    let level = if path.extension().is_some_and(|extension| extension.eq_ignore_ascii_case("png")) {
+        let image = image::open(path).map_err(|error| format!("Couldn't read {}: {}", path.display(), error))?;
+        level_from_image(&image.to_rgba8(), name, grid)
+    } else {
+        let text = fs::read_to_string(path).map_err(|error| format!("Couldn't read {}: {}", path.display(), error))?;
+        level_from_ascii(&text, name, grid)
+    };
+    level.map_err(|error| format!("{} can't be imported: {}", path.display(), error))
+}
+
+// Each opaque pixel becomes a standard brick of that color, transparent pixels are left empty
+pub fn level_from_image(image: &RgbaImage, name: &str, grid: &LevelGrid) -> Result<Level, String> {
+    let left = left_column(image.width(), image.height(), grid)?;
+
+    let mut level = Level::empty(name);
+    for (x, y, pixel) in image.enumerate_pixels() {
+        let [red, green, blue, alpha] = pixel.0;
+        if alpha < OPAQUE_ALPHA {
+            continue;
+        }
+        level.bricks.push(LevelBrick {
+            column: left + x,
+            row: y,
+            kind: BrickKind::Standard,
+            color: Some([red, green, blue]),
+        });
+    }
+    Ok(level)
+}
+
+// One line per row of bricks: `S` is a strong brick, `X` an indestructible one, a space or `.`
+// is left empty and anything else is a standard brick
+pub fn level_from_ascii(text: &str, name: &str, grid: &LevelGrid) -> Result<Level, String> {
+    let lines: Vec<&str> = text.trim_end().lines().map(str::trim_end).collect();
+    let width = lines.iter().map(|line| line.chars().count()).max().unwrap_or(0);
+    let left = left_column(width as u32, lines.len() as u32, grid)?;
+
+    let mut level = Level::empty(name);
+    for (row, line) in lines.iter().enumerate() {
+        for (column, character) in line.chars().enumerate() {
+            let kind = match character {
+                ' ' | '.' => continue,
+                'S' => BrickKind::Strong,
+                'X' => BrickKind::Indestructible,
+                _ => BrickKind::Standard,
+            };
+            let cell = GridCell {
+                column: left + column as u32,
+                row: row as u32,
+            };
+            level.set(cell, Some(kind));
+        }
+    }
+    Ok(level)
+}
+
+// The column the art starts at to be centered on the grid, if it fits
+fn left_column(width: u32, height: u32, grid: &LevelGrid) -> Result<u32, String> {
+    if width > grid.columns || height > grid.rows {
+        return Err(format!(
+            "it's {}x{} but levels can be at most {}x{}",
+            width, height, grid.columns, grid.rows
+        ));
+    }
+    Ok((grid.columns - width) / 2)
+}
+
+#[cfg(test)]
+mod tests {
+    use image::Rgba;
+
+    use super::*;
+    use crate::playfield::Playfield;
+
+    #[test]
+    fn opaque_pixels_become_bricks_of_their_color() {
+        let grid = LevelGrid::new(&Playfield::default());
+        let mut image = RgbaImage::new(2, 2);
+        image.put_pixel(0, 0, Rgba([255, 136, 0, 255]));
+        image.put_pixel(1, 1, Rgba([10, 20, 30, 200]));
+        // Transparent, and too faint to count
+        image.put_pixel(1, 0, Rgba([255, 255, 255, 0]));
+        image.put_pixel(0, 1, Rgba([255, 255, 255, OPAQUE_ALPHA - 1]));
+
+        let level = level_from_image(&image, "Art", &grid).unwrap();
+        let left = (grid.columns - 2) / 2;
+        assert_eq!(
+            level.bricks,
+            vec![
+                LevelBrick {
+                    column: left,
+                    row: 0,
+                    kind: BrickKind::Standard,
+                    color: Some([255, 136, 0]),
+                },
+                LevelBrick {
+                    column: left + 1,
+                    row: 1,
+                    kind: BrickKind::Standard,
+                    color: Some([10, 20, 30]),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn art_bigger_than_the_grid_is_rejected() {
+        let grid = LevelGrid::new(&Playfield::default());
+        let wide = RgbaImage::from_pixel(grid.columns + 1, 1, Rgba([255, 255, 255, 255]));
+        let tall = RgbaImage::from_pixel(1, grid.rows + 1, Rgba([255, 255, 255, 255]));
+        assert!(level_from_image(&wide, "Wide", &grid).is_err());
+        assert!(level_from_image(&tall, "Tall", &grid).is_err());
+
+        let full = RgbaImage::from_pixel(grid.columns, grid.rows, Rgba([255, 255, 255, 255]));
+        assert_eq!(level_from_image(&full, "Full", &grid).unwrap().bricks.len(), (grid.columns * grid.rows) as usize);
+    }
+
+    #[test]
+    fn ascii_art_picks_brick_kinds_by_character() {
+        let grid = LevelGrid::new(&Playfield::default());
+        let level = level_from_ascii("S.X\n #", "Ascii", &grid).unwrap();
+        let left = (grid.columns - 3) / 2;
+        let kind = |column, row| level.kind_at(GridCell { column: left + column, row });
+        assert_eq!(kind(0, 0), Some(BrickKind::Strong));
+        assert_eq!(kind(1, 0), None);
+        assert_eq!(kind(2, 0), Some(BrickKind::Indestructible));
+        assert_eq!(kind(0, 1), None);
+        assert_eq!(kind(1, 1), Some(BrickKind::Standard));
+        assert_eq!(level.bricks.len(), 3);
+    }
+}
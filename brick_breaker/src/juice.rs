@@ -0,0 +1,96 @@
+use std::marker::PhantomData;
+use std::time::Duration;
+
+use bevy::prelude::*;
+use common::juice::{HitStop, JuiceStyle, Juiced, Shake};
+
+use crate::components::{Ball, Paddle, Velocity};
+use crate::events::{BallHitPaddle, BallLost, BrickDamaged, BrickDestroyed, WallBounce};
+use crate::BALL_SPEED;
+
+pub use common::juice::JuiceSettings;
+
+// How long the game freezes when a brick breaks
+const HIT_STOP: Duration = Duration::from_millis(50);
+
+// Game feel: the camera shakes with every impact, harder the harder the ball hits, the game
+// freezes for a moment when a brick breaks, the paddle squashes when the ball lands on it and
+// the ball stretches as it flies
+pub struct JuicePlugin {
+    pub settings: JuiceSettings,
+}
+
+impl Plugin for JuicePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins(common::juice::JuicePlugin::<Ball> {
+            settings: self.settings,
+            style: JuiceStyle {
+                stretch: 0.25,
+                speed: BALL_SPEED,
+                squash: 0.3,
+                squash_axis: Vec2::Y,
+            },
+            balls: PhantomData,
+        })
+        .add_systems(Update, add_paddle_juice)
+        .add_systems(Update, (shake_on_impact, start_hit_stop, squash_paddle));
+    }
+}
+
+fn add_paddle_juice(mut commands: Commands, added: Query<Entity, Added<Paddle>>) {
+    for entity in added.iter() {
+        commands.entity(entity).insert(Juiced::default());
+    }
+}
+
+fn shake_on_impact(
+    mut paddle_hits: EventReader<BallHitPaddle>,
+    mut wall_bounces: EventReader<WallBounce>,
+    mut brick_damaged: EventReader<BrickDamaged>,
+    mut brick_destroyed: EventReader<BrickDestroyed>,
+    mut balls_lost: EventReader<BallLost>,
+    balls: Query<&Velocity, With<Ball>>,
+    mut shake: ResMut<Shake>,
+) {
+    // How hard the ball hit, by how fast it was going into the surface compared to a ball at
+    // BALL_SPEED coming straight in, so glancing blows barely shake at all
+    let impact = |ball, normal: Vec2| {
+        let velocity = balls.get(ball).map_or(Vec2::ZERO, |velocity| velocity.0.truncate());
+        (velocity.dot(normal).abs() / BALL_SPEED).min(2.)
+    };
+    for event in paddle_hits.read() {
+        shake.add(0.1 * impact(event.ball, Vec2::Y));
+    }
+    for event in wall_bounces.read() {
+        shake.add(0.05 * impact(event.ball, event.normal));
+    }
+    for _ in brick_damaged.read() {
+        shake.add(0.1);
+    }
+    // Tougher bricks break harder
+    for event in brick_destroyed.read() {
+        shake.add(0.15 + 0.1 * event.kind.hits().unwrap_or(1) as f32);
+    }
+    for _ in balls_lost.read() {
+        shake.add(0.5);
+    }
+}
+
+fn start_hit_stop(
+    mut brick_destroyed: EventReader<BrickDestroyed>,
+    settings: Res<JuiceSettings>,
+    mut hit_stop: ResMut<HitStop>,
+    mut time: ResMut<Time<Virtual>>,
+) {
+    if brick_destroyed.read().count() > 0 && settings.hit_stop {
+        hit_stop.start(HIT_STOP, &mut time);
+    }
+}
+
+fn squash_paddle(mut paddle_hits: EventReader<BallHitPaddle>, mut paddles: Query<&mut Juiced, With<Paddle>>) {
+    for event in paddle_hits.read() {
+        if let Ok(mut juiced) = paddles.get_mut(event.paddle) {
+            juiced.squash();
+        }
+    }
+}
@@ -0,0 +1,238 @@
+use std::collections::HashSet;
+use std::fs;
+use std::path::Path;
+
+use bevy::prelude::*;
+use ron::ser::PrettyConfig;
+use serde::{Deserialize, Serialize};
+
+use crate::components::{BrickKind, GridCell};
+use crate::playfield::Playfield;
+use crate::{BRICK_HEIGHT, BRICK_PAD_BOTTOM, BRICK_PAD_LR, BRICK_PAD_TOP, BRICK_SPACE, BRICK_WIDTH};
+
+// A layout of bricks on the level grid. Levels are saved as RON files:
+//
+//   (
+//       name: "Fortress",
+//       bricks: [
+//           (column: 0, row: 0, kind: Strong),
+//           (column: 1, row: 0, kind: Indestructible),
+//           (column: 2, row: 0, kind: Standard, color: Some((255, 136, 0))),
+//       ],
+//   )
+//
+// Row 0 is the top of the grid. Bricks are colored by kind unless they have their own color.
+#[derive(Resource, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Level {
+    pub name: String,
+    pub bricks: Vec<LevelBrick>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LevelBrick {
+    pub column: u32,
+    pub row: u32,
+    pub kind: BrickKind,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub color: Option<[u8; 3]>,
+}
+
+impl LevelBrick {
+    pub fn cell(&self) -> GridCell {
+        GridCell {
+            column: self.column,
+            row: self.row,
+        }
+    }
+
+    pub fn display_color(&self) -> Color {
+        match self.color {
+            Some([red, green, blue]) => Color::srgb_u8(red, green, blue),
+            None => self.kind.color(),
+        }
+    }
+}
+
+impl Level {
+    // Every cell filled with a standard brick
+    pub fn classic(grid: &LevelGrid) -> Self {
+        let bricks = (0..grid.rows)
+            .flat_map(|row| (0..grid.columns).map(move |column| (column, row)))
+            .map(|(column, row)| LevelBrick {
+                column,
+                row,
+                kind: BrickKind::Standard,
+                color: None,
+            })
+            .collect();
+        Self {
+            name: "Classic".to_string(),
+            bricks,
+        }
+    }
+
+    pub fn empty(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            bricks: Vec::new(),
+        }
+    }
+
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let text = fs::read_to_string(path).map_err(|error| format!("Couldn't read {}: {}", path.display(), error))?;
+        ron::from_str(&text).map_err(|error| format!("{} isn't a valid level: {}", path.display(), error))
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        let text = ron::ser::to_string_pretty(self, PrettyConfig::new().depth_limit(2)).expect("levels are always serializable");
+        fs::write(path, text + "\n").map_err(|error| format!("Couldn't write {}: {}", path.display(), error))
+    }
+
+    // Checks every brick is on the grid and no two share a cell
+    pub fn validate(&self, grid: &LevelGrid) -> Result<(), String> {
+        let mut taken = HashSet::new();
+        for brick in &self.bricks {
+            if !grid.contains(brick.cell()) {
+                return Err(format!(
+                    "brick at column {}, row {} is off the {}x{} grid",
+                    brick.column, brick.row, grid.columns, grid.rows
+                ));
+            }
+            if !taken.insert(brick.cell()) {
+                return Err(format!("two bricks at column {}, row {}", brick.column, brick.row));
+            }
+        }
+        Ok(())
+    }
+
+    pub fn kind_at(&self, cell: GridCell) -> Option<BrickKind> {
+        self.bricks.iter().find(|brick| brick.cell() == cell).map(|brick| brick.kind)
+    }
+
+    // Puts a brick of `kind` in the cell, replacing what was there, or empties it if `kind` is `None`.
+    // A brick that's already there keeps its color if it stays the same kind, and otherwise
+    // takes on the kind's color so it can be told apart
+    pub fn set(&mut self, cell: GridCell, kind: Option<BrickKind>) {
+        let color = self
+            .bricks
+            .iter()
+            .find(|brick| brick.cell() == cell && Some(brick.kind) == kind)
+            .and_then(|brick| brick.color);
+        self.bricks.retain(|brick| brick.cell() != cell);
+        if let Some(kind) = kind {
+            self.bricks.push(LevelBrick {
+                column: cell.column,
+                row: cell.row,
+                kind,
+                color,
+            });
+        }
+    }
+
+    // Bricks that have to be broken to clear the level
+    pub fn breakable(&self) -> impl Iterator<Item = &LevelBrick> {
+        self.bricks.iter().filter(|brick| brick.kind.hits().is_some())
+    }
+}
+
+// The cells bricks can go in, centered across the top of the playfield
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LevelGrid {
+    pub columns: u32,
+    pub rows: u32,
+    // Center of the top left cell
+    origin: Vec2,
+}
+
+impl LevelGrid {
+    pub const CELL_SIZE: Vec2 = Vec2::new(BRICK_WIDTH + BRICK_SPACE, BRICK_HEIGHT + BRICK_SPACE * 2.);
+
+    pub fn new(playfield: &Playfield) -> Self {
+        let columns = ((playfield.width - BRICK_PAD_LR * 2. + BRICK_SPACE) / Self::CELL_SIZE.x).floor() as u32;
+        let rows = ((playfield.height - BRICK_PAD_TOP - BRICK_PAD_BOTTOM) / Self::CELL_SIZE.y).floor() as u32;
+        let width = columns as f32 * Self::CELL_SIZE.x;
+        Self {
+            columns,
+            rows,
+            origin: Vec2::new(
+                (Self::CELL_SIZE.x - width) / 2.,
+                playfield.top() - BRICK_PAD_TOP - Self::CELL_SIZE.y / 2.,
+            ),
+        }
+    }
+
+    pub fn contains(&self, cell: GridCell) -> bool {
+        cell.column < self.columns && cell.row < self.rows
+    }
+
+    pub fn cell_center(&self, cell: GridCell) -> Vec2 {
+        self.origin + Vec2::new(cell.column as f32, -(cell.row as f32)) * Self::CELL_SIZE
+    }
+
+    // The cell under a point on the playfield, if it's on the grid
+    pub fn cell_at(&self, position: Vec2) -> Option<GridCell> {
+        let offset = ((position - self.origin) * Vec2::new(1., -1.) / Self::CELL_SIZE).round();
+        if offset.min_element() < 0. {
+            return None;
+        }
+        let cell = GridCell {
+            column: offset.x as u32,
+            row: offset.y as u32,
+        };
+        self.contains(cell).then_some(cell)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn brick(column: u32, row: u32) -> LevelBrick {
+        LevelBrick {
+            column,
+            row,
+            kind: BrickKind::Standard,
+            color: None,
+        }
+    }
+
+    #[test]
+    fn the_classic_layout_is_valid() {
+        let grid = LevelGrid::new(&Playfield::default());
+        assert_eq!(Level::classic(&grid).validate(&grid), Ok(()));
+    }
+
+    #[test]
+    fn bricks_off_the_grid_are_rejected() {
+        let grid = LevelGrid::new(&Playfield::default());
+        for (column, row) in [(grid.columns, 0), (0, grid.rows), (u32::MAX, u32::MAX)] {
+            let mut level = Level::empty("Off the grid");
+            level.bricks = vec![brick(0, 0), brick(column, row)];
+            assert!(level.validate(&grid).is_err(), "column {}, row {}", column, row);
+        }
+    }
+
+    #[test]
+    fn changing_a_bricks_kind_drops_its_color() {
+        let cell = GridCell { column: 1, row: 2 };
+        let mut level = Level::empty("Painted");
+        level.bricks = vec![LevelBrick {
+            color: Some([255, 136, 0]),
+            ..brick(1, 2)
+        }];
+
+        level.set(cell, Some(BrickKind::Standard));
+        assert_eq!(level.bricks[0].color, Some([255, 136, 0]));
+        level.set(cell, Some(BrickKind::Indestructible));
+        assert_eq!(level.bricks[0].color, None);
+        assert_eq!(level.kind_at(cell), Some(BrickKind::Indestructible));
+    }
+
+    #[test]
+    fn two_bricks_in_a_cell_are_rejected() {
+        let grid = LevelGrid::new(&Playfield::default());
+        let mut level = Level::empty("Doubled up");
+        level.bricks = vec![brick(1, 2), brick(1, 2)];
+        assert!(level.validate(&grid).is_err());
+    }
+}
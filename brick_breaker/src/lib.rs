@@ -0,0 +1,634 @@
+use std::time::Duration;
+
+use bevy::{
+    input::InputPlugin,
+    prelude::*,
+    sprite::{MaterialMesh2dBundle, Mesh2dHandle},
+    math::bounding::{Aabb2d, BoundingCircle, BoundingVolume, IntersectsVolume},
+    state::app::StatesPlugin,
+    time::TimeUpdateStrategy,
+};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+pub mod arena;
+pub mod assets;
+pub mod components;
+pub mod console;
+pub mod controller;
+pub mod debug;
+pub mod editor;
+pub mod events;
+pub mod flow;
+pub mod generator;
+pub mod highscore;
+pub mod particles;
+pub mod import;
+pub mod juice;
+pub mod level;
+pub mod remote;
+pub mod rewind;
+pub mod timescale;
+pub mod title;
+pub mod trail;
+
+pub use common::playfield;
+
+use arena::*;
+use assets::*;
+use components::*;
+use controller::*;
+use events::*;
+use level::*;
+use playfield::*;
+
+pub const PADDLE_WIDTH: f32 = 100.;
+pub const PADDLE_HEIGHT: f32 = 10.;
+pub const PADDLE_PAD: f32 = 30.;
+pub const PADDLE_SPEED: f32 = 500.;
+// The wide paddle power-up, and how many seconds it lasts
+pub const WIDE_PADDLE_WIDTH: f32 = PADDLE_WIDTH * 1.5;
+pub const WIDE_PADDLE_TIME: f32 = 15.;
+pub const BALL_RADIUS: f32 = 8.;
+pub const BALL_SPEED: f32 = 400.;
+pub const BRICK_WIDTH: f32 = 100.;
+pub const BRICK_HEIGHT: f32 = 30.;
+pub const BRICK_PAD_LR: f32 = 30.;
+pub const BRICK_PAD_TOP: f32 = 50.0;
+pub const BRICK_PAD_BOTTOM: f32 = 300.0;
+pub const BRICK_SPACE: f32 = 1.0;
+// Balls the player gets each game
+pub const BALLS: u32 = 3;
+// Range of launch angles off straight up, to either side
+pub const MIN_LAUNCH_ANGLE: f32 = std::f32::consts::PI / 12.;
+pub const MAX_LAUNCH_ANGLE: f32 = std::f32::consts::FRAC_PI_4;
+
+// How long a tick of the simulation lasts, 60 to the second
+pub const TICK: Duration = Duration::from_nanos(16_666_667);
+// How long a frame lasts when running headless, however fast it's actually running. Every
+// frame after the first runs a tick
+pub const HEADLESS_FRAME: Duration = TICK;
+
+// The brick breaker game itself, without a window or title screen so it can also run headless.
+// A level is put on the field whenever the `Screen` changes to `Demo` or `Playing`, and again
+// on a `RestartGame`. Clearing the level or losing the last ball ends play in the
+// `LevelComplete` or `GameOver` state, and it's up to the screen to take it from there.
+// Play moves on in FixedUpdate ticks, steered by the controllers every Update
+#[derive(Default)]
+pub struct BrickBreakerPlugin {
+    // Seeds the launch angles, random if not set
+    pub seed: Option<u64>,
+    // The level to play, the classic layout if not set
+    pub level: Option<Level>,
+}
+
+impl Plugin for BrickBreakerPlugin {
+    fn build(&self, app: &mut App) {
+        let playfield = Playfield::default();
+        let level = self
+            .level
+            .clone()
+            .unwrap_or_else(|| Level::classic(&LevelGrid::new(&playfield)));
+        let seed = self.seed.unwrap_or_else(rand::random);
+
+        app.insert_resource(playfield)
+            .insert_resource(Arena::classic(&playfield))
+            .insert_resource(level)
+            .insert_resource(GameRng(StdRng::seed_from_u64(seed)))
+            .insert_resource(GameSeed(seed))
+            .insert_resource(Time::<Fixed>::from_duration(TICK))
+            .init_resource::<GodMode>()
+            .init_resource::<GameAssets>()
+            .init_resource::<Score>()
+            .init_resource::<LastContact>()
+            .insert_resource(Lives(BALLS))
+            .init_resource::<ControllerSetup>()
+            .init_state::<Screen>()
+            .add_sub_state::<GameState>()
+            .enable_state_scoped_entities::<Screen>()
+            .enable_state_scoped_entities::<GameState>()
+            .add_event::<BallHitPaddle>()
+            .add_event::<BrickDamaged>()
+            .add_event::<BrickDestroyed>()
+            .add_event::<WallBounce>()
+            .add_event::<BallLost>()
+            .add_event::<RestartGame>()
+            .add_event::<NextLevel>()
+            .add_event::<GameFinished>()
+            .add_systems(Startup, setup)
+            .add_systems(OnEnter(Screen::Demo), spawn_level)
+            .add_systems(OnEnter(Screen::Playing), spawn_level)
+            .add_systems(
+                Update,
+                drive_paddle.run_if(in_state(GameState::Start).or_else(in_state(GameState::InGame))),
+            )
+            .add_systems(
+                FixedUpdate,
+                (
+                    steer_paddle,
+                    update_position,
+                    wear_off_wide_paddle,
+                    clamp_paddle,
+                    ball_follow.run_if(in_state(GameState::Start)),
+                    check_collisions,
+                    reset_lost_ball,
+                    check_level_complete,
+                )
+                    .chain()
+                    .run_if(in_state(GameState::Start).or_else(in_state(GameState::InGame))),
+            )
+            .add_systems(Update, (clear_level, spawn_level).chain().run_if(on_event::<RestartGame>()))
+            .add_systems(Update, log_gameplay_events);
+    }
+}
+
+// The game without a window, advancing a fixed 60 FPS frame per update. More plugins can be
+// added before it's first updated
+pub fn headless_app(game: BrickBreakerPlugin) -> App {
+    let mut app = App::new();
+    app.add_plugins((MinimalPlugins, InputPlugin, StatesPlugin))
+        .insert_resource(TimeUpdateStrategy::ManualDuration(HEADLESS_FRAME))
+        // Nothing gets rendered, but the game still creates meshes and materials for its entities
+        .init_resource::<Assets<Mesh>>()
+        .init_resource::<Assets<ColorMaterial>>()
+        .add_plugins(game);
+    app
+}
+
+fn setup(
+    mut commands: Commands,
+    mut assets: ResMut<GameAssets>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    arena: Res<Arena>,
+) {
+    spawn_arena(&mut commands, &mut assets, &mut materials, &arena);
+}
+
+// Starts a new game: puts a fresh paddle, ball and set of bricks on the field for the current
+// screen, with the paddle played by whoever is in `ControllerSetup`
+#[allow(clippy::too_many_arguments)]
+pub fn spawn_level(
+    mut commands: Commands,
+    mut assets: ResMut<GameAssets>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    playfield: Res<Playfield>,
+    level: Res<Level>,
+    mut controllers: ResMut<ControllerSetup>,
+    screen: Res<State<Screen>>,
+    mut score: ResMut<Score>,
+    mut lives: ResMut<Lives>,
+) {
+    score.0 = 0;
+    lives.0 = BALLS;
+
+    // Everything goes away again when the screen changes
+    let scope = StateScoped(*screen.get());
+    let white = assets.material(&mut materials, Color::WHITE);
+
+    // Paddle
+    let mut paddle = commands.spawn((
+        MaterialMesh2dBundle {
+            mesh: Mesh2dHandle(assets.square.clone()),
+            material: white,
+            transform: Transform {
+                translation: Vec3::new(
+                    0.0,
+                    playfield.bottom() + PADDLE_PAD,
+                    0.0
+                ),
+                scale: Vec3::new(PADDLE_WIDTH, PADDLE_HEIGHT, 1.0),
+                ..default()
+            },
+            ..default()
+        },
+        Paddle,
+        Collider,
+        Velocity(Vec3::new(0.0, 0.0, 0.0)),
+        Steering::default(),
+        scope,
+    ));
+    if let Some(controller) = controllers.paddle.take() {
+        paddle.insert(controller);
+    }
+
+    // Ball
+    let position = Vec3::new(0.0, playfield.bottom() + PADDLE_PAD + PADDLE_HEIGHT + BALL_RADIUS/2., 0.0);
+    spawn_ball(&mut commands, &mut assets, &mut materials, position, Vec3::ZERO, *screen.get());
+
+    spawn_bricks(&mut commands, &mut assets, &mut materials, &playfield, &level, *screen.get());
+}
+
+// Takes the paddle, ball and bricks off the field so `spawn_level` can start over, keeping
+// whoever was playing the paddle
+pub fn clear_level(world: &mut World) {
+    let paddle = world.query_filtered::<Entity, With<Paddle>>().iter(world).next();
+    if let Some(controller) = paddle.and_then(|paddle| world.entity_mut(paddle).take::<Controller>()) {
+        world.resource_mut::<ControllerSetup>().paddle = Some(controller);
+    }
+
+    let level: Vec<Entity> = world
+        .query_filtered::<Entity, Or<(With<Paddle>, With<Ball>, With<Brick>)>>()
+        .iter(world)
+        .collect();
+    for entity in level {
+        world.despawn(entity);
+    }
+    world.resource_mut::<NextState<GameState>>().set(GameState::Start);
+}
+
+// Puts a ball on the field, to be cleared away when the screen changes
+pub fn spawn_ball(
+    commands: &mut Commands,
+    assets: &mut GameAssets,
+    materials: &mut Assets<ColorMaterial>,
+    position: Vec3,
+    velocity: Vec3,
+    screen: Screen,
+) -> Entity {
+    commands.spawn((
+        MaterialMesh2dBundle {
+            mesh: Mesh2dHandle(assets.circle.clone()),
+            material: assets.material(materials, Color::WHITE),
+            transform: Transform::from_translation(position).with_scale(Vec2::splat(BALL_RADIUS).extend(1.)),
+            ..default()
+        },
+        Ball,
+        Velocity(velocity),
+        StateScoped(screen),
+    ))
+    .id()
+}
+
+// Lays the level's bricks out on the field, to be cleared away when the screen changes
+pub fn spawn_bricks(
+    commands: &mut Commands,
+    assets: &mut GameAssets,
+    materials: &mut Assets<ColorMaterial>,
+    playfield: &Playfield,
+    level: &Level,
+    screen: Screen,
+) {
+    let grid = LevelGrid::new(playfield);
+    for brick in &level.bricks {
+        let mut entity = commands.spawn((
+            MaterialMesh2dBundle {
+                mesh: Mesh2dHandle(assets.square.clone()),
+                material: assets.material(materials, brick.display_color()),
+                transform: Transform {
+                    translation: grid.cell_center(brick.cell()).extend(0.0),
+                    scale: Vec3::new(BRICK_WIDTH, BRICK_HEIGHT, 1.0),
+                    ..default()
+                },
+                ..default()
+            },
+            Brick(brick.kind),
+            brick.cell(),
+            Collider,
+            StateScoped(screen),
+        ));
+        if let Some(hits) = brick.kind.hits() {
+            entity.insert(Health(hits));
+        }
+    }
+}
+
+// Sends the ball off the paddle at the start of a turn, up at a random angle
+pub fn launch_ball(ball_velocity: &mut Velocity, rng: &mut GameRng) {
+    let mut angle = rng.0.gen_range(MIN_LAUNCH_ANGLE..=MAX_LAUNCH_ANGLE);
+    if rng.0.gen() {
+        angle = -angle;
+    }
+    ball_velocity.0 = (Vec2::from_angle(angle).rotate(Vec2::Y) * BALL_SPEED).extend(0.);
+}
+
+fn update_position(time: Res<Time>, mut query: Query<(&Velocity, &mut Transform)>) {
+    for (velocity, mut transform) in query.iter_mut() {
+        transform.translation += velocity.0 * time.delta_seconds();
+    }
+}
+
+fn ball_follow(
+    mut ball_query: Query<(&Velocity, &mut Transform), With<Ball>>,
+    paddle_query: Query<&Transform, (With<Paddle>, Without<Ball>)>,
+) {
+    // This system only runs if the game state is Start
+
+    let paddle_transform = paddle_query.single();
+
+    // Any other balls in play carry on by themselves
+    for (velocity, mut ball_transform) in ball_query.iter_mut() {
+        if velocity.0 == Vec3::ZERO {
+            ball_transform.translation.x = paddle_transform.translation.x;
+        }
+    }
+}
+
+fn wear_off_wide_paddle(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut query: Query<(Entity, &mut Transform, &mut WidePaddle)>,
+) {
+    for (entity, mut transform, mut wide) in query.iter_mut() {
+        if wide.0.tick(time.delta()).just_finished() {
+            transform.scale.x = PADDLE_WIDTH;
+            commands.entity(entity).remove::<WidePaddle>();
+        }
+    }
+}
+
+fn clamp_paddle(
+    mut query: Query<(&mut Velocity, &mut Transform), With<Paddle>>,
+    playfield: Res<Playfield>,
+) {
+    for (mut velocity, mut transform) in query.iter_mut() {
+        // The paddle is as wide as its scale, which power-ups change
+        let half_width = transform.scale.x / 2.;
+        if transform.translation.x - half_width < playfield.left() {
+            // Clamp left wall
+            velocity.0.x = 0.;
+            transform.translation.x = playfield.left() + half_width + 1.
+        }
+        if transform.translation.x + half_width > playfield.right() {
+            // Clamp right wall
+            velocity.0.x = 0.;
+            transform.translation.x = playfield.right() - half_width - 1.
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
+pub fn check_collisions(
+    mut commands: Commands,
+    mut ball_query: Query<(Entity, &mut Velocity, &mut Transform), With<Ball>>,
+    mut collider_query: Query<(Entity, &Transform, Option<&Brick>, Option<&mut Health>, Option<&Paddle>, Option<&Wall>, Option<&Handle<ColorMaterial>>), (With<Collider>, Without<Ball>)>,
+    materials: Res<Assets<ColorMaterial>>,
+    mut paddle_hits: EventWriter<BallHitPaddle>,
+    mut brick_damaged: EventWriter<BrickDamaged>,
+    mut brick_destroyed: EventWriter<BrickDestroyed>,
+    mut wall_bounces: EventWriter<WallBounce>,
+    mut balls_lost: EventWriter<BallLost>,
+    playfield: Res<Playfield>,
+    mut score: ResMut<Score>,
+    mut last_contact: ResMut<LastContact>,
+    god_mode: Res<GodMode>,
+) {
+    for (ball, mut ball_velocity, mut ball_transform) in ball_query.iter_mut() {
+        for (entity, transform, maybe_brick, maybe_health, maybe_paddle, maybe_wall, maybe_material) in collider_query.iter_mut() {
+            // Broken by another ball this frame, and on its way out
+            if maybe_health.as_ref().is_some_and(|health| health.0 == 0) {
+                continue;
+            }
+            let contact = ball_collision(
+                BoundingCircle::new(ball_transform.translation.truncate(), BALL_RADIUS),
+                transform,
+            );
+            let Some(contact) = contact else {
+                continue;
+            };
+            last_contact.0 = Some(common::components::Contact {
+                point: contact.point,
+                normal: contact.normal,
+            });
+
+            // Push the ball back out of whatever it hit
+            ball_transform.translation += (contact.normal * contact.depth).extend(0.);
+
+            // Sends gameplay events so that other systems can react to the collision
+            if maybe_paddle.is_some() {
+                let offset = (ball_transform.translation.x - transform.translation.x) / (transform.scale.x / 2.);
+                paddle_hits.send(BallHitPaddle {
+                    ball,
+                    paddle: entity,
+                    offset: offset.clamp(-1., 1.),
+                });
+            }
+
+            if maybe_wall.is_some() {
+                wall_bounces.send(WallBounce {
+                    ball,
+                    wall: entity,
+                    point: contact.point,
+                    normal: contact.normal,
+                });
+            }
+
+            // Bricks lose health on collision and are despawned once it runs out
+            if let (Some(Brick(kind)), Some(mut health)) = (maybe_brick, maybe_health) {
+                let position = transform.translation.truncate();
+                let color = maybe_material
+                    .and_then(|material| materials.get(material))
+                    .map_or(kind.color(), |material| material.color);
+                health.0 = health.0.saturating_sub(1);
+                if health.0 == 0 {
+                    commands.entity(entity).despawn();
+                    brick_destroyed.send(BrickDestroyed {
+                        brick: entity,
+                        position,
+                        kind: *kind,
+                        color,
+                    });
+                    score.0 += kind.points();
+                } else {
+                    brick_damaged.send(BrickDamaged {
+                        brick: entity,
+                        position,
+                        kind: *kind,
+                        color,
+                        remaining: health.0,
+                    });
+                }
+            }
+
+            // Reflect the ball's velocity off the face it hit, but only if it's moving into it.
+            // This prevents the ball from getting stuck inside the bar
+            let approach = ball_velocity.0.truncate().dot(contact.normal);
+            if approach < 0. {
+                ball_velocity.0 -= (2. * approach * contact.normal).extend(0.);
+            }
+        }
+
+        if god_mode.0 {
+            // Nothing gets past the bottom, it's just another wall
+            if ball_transform.translation.y - BALL_RADIUS < playfield.bottom() && ball_velocity.0.y < 0. {
+                ball_velocity.0.y = -ball_velocity.0.y;
+            }
+        } else if ball_transform.translation.y + BALL_RADIUS < playfield.bottom() {
+            // The ball got past the paddle and fell out of the bottom of the arena
+            balls_lost.send(BallLost {
+                ball,
+                position: ball_transform.translation.truncate(),
+            });
+        }
+    }
+}
+
+fn reset_lost_ball(
+    mut commands: Commands,
+    mut balls_lost: EventReader<BallLost>,
+    mut ball_query: Query<(&mut Velocity, &mut Transform), With<Ball>>,
+    paddle_query: Query<&Transform, (With<Paddle>, Without<Ball>)>,
+    mut next_state: ResMut<NextState<GameState>>,
+    mut lives: ResMut<Lives>,
+) {
+    let paddle_transform = paddle_query.single();
+
+    // Losing the ball puts it back on the paddle, unless it was the last one. While there's
+    // more than one ball in play, a lost ball is just gone
+    let mut in_play = ball_query.iter().count();
+    for event in balls_lost.read() {
+        if in_play > 1 {
+            commands.entity(event.ball).despawn();
+            in_play -= 1;
+            continue;
+        }
+        if let Ok((mut velocity, mut transform)) = ball_query.get_mut(event.ball) {
+            lives.0 = lives.0.saturating_sub(1);
+            return_ball(&mut velocity, &mut transform, paddle_transform, &mut next_state);
+            if lives.0 == 0 {
+                next_state.set(GameState::GameOver);
+            }
+        }
+    }
+}
+
+// Play stops once every breakable brick is gone, even if the last ball went with it
+pub fn check_level_complete(remaining: Query<(), With<Health>>, mut next_state: ResMut<NextState<GameState>>) {
+    if remaining.is_empty() {
+        next_state.set(GameState::LevelComplete);
+    }
+}
+
+// Puts the ball back on the paddle, waiting to be launched
+pub fn return_ball(
+    velocity: &mut Velocity,
+    transform: &mut Transform,
+    paddle_transform: &Transform,
+    next_state: &mut NextState<GameState>,
+) {
+    velocity.0 = Vec3::ZERO;
+    transform.translation.y = paddle_transform.translation.y + PADDLE_HEIGHT + BALL_RADIUS/2.;
+    next_state.set(GameState::Start);
+}
+
+fn ball_collision(ball: BoundingCircle, collider: &Transform) -> Option<Contact> {
+    // Work in the collider's local space so that rotated colliders are just boxes
+    let local_center = (collider.rotation.inverse() * (ball.center() - collider.translation.truncate()).extend(0.)).truncate();
+    let bounding_box = Aabb2d::new(Vec2::ZERO, collider.scale.truncate() / 2.);
+    if !BoundingCircle::new(local_center, ball.radius()).intersects(&bounding_box) {
+        return None;
+    }
+
+    let closest = bounding_box.closest_point(local_center);
+    let offset = local_center - closest;
+    let (normal, depth) = if offset == Vec2::ZERO {
+        // The ball's center is already inside the collider, so push it out through the nearest face
+        let inside = bounding_box.half_size() - local_center.abs();
+        if inside.x < inside.y {
+            (Vec2::new(local_center.x.signum(), 0.), inside.x + ball.radius())
+        } else {
+            (Vec2::new(0., local_center.y.signum()), inside.y + ball.radius())
+        }
+    } else {
+        (offset.normalize(), ball.radius() - offset.length())
+    };
+
+    Some(Contact {
+        point: collider.translation.truncate() + (collider.rotation * closest.extend(0.)).truncate(),
+        normal: (collider.rotation * normal.extend(0.)).truncate(),
+        depth,
+    })
+}
+
+fn log_gameplay_events(
+    mut paddle_hits: EventReader<BallHitPaddle>,
+    mut brick_damaged: EventReader<BrickDamaged>,
+    mut brick_destroyed: EventReader<BrickDestroyed>,
+    mut wall_bounces: EventReader<WallBounce>,
+    mut balls_lost: EventReader<BallLost>,
+) {
+    for event in paddle_hits.read() {
+        debug!("Ball {:?} hit paddle {:?} at offset {:.2}", event.ball, event.paddle, event.offset);
+    }
+    for event in brick_damaged.read() {
+        debug!("{:?} brick {:?} at {} damaged, {} hits left", event.kind, event.brick, event.position, event.remaining);
+    }
+    for event in brick_destroyed.read() {
+        debug!("{:?} brick at {} destroyed", event.kind, event.position);
+    }
+    for event in wall_bounces.read() {
+        debug!("Ball {:?} bounced off wall {:?} at {} (normal {})", event.ball, event.wall, event.point, event.normal);
+    }
+    for event in balls_lost.read() {
+        debug!("Ball {:?} lost at {}", event.ball, event.position);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy::input::{
+        keyboard::{Key, KeyboardInput},
+        ButtonState,
+    };
+
+    #[test]
+    fn a_brick_two_balls_hit_at_once_only_breaks_once() {
+        let grid = LevelGrid::new(&Playfield::default());
+        let cell = GridCell { column: 3, row: 2 };
+        let mut level = Level::empty("One brick");
+        level.set(cell, Some(BrickKind::Standard));
+        let mut app = headless_app(BrickBreakerPlugin {
+            seed: Some(1),
+            level: Some(level),
+        });
+        app.world_mut().resource_mut::<NextState<Screen>>().set(Screen::Playing);
+        app.update();
+        app.world_mut().resource_mut::<NextState<GameState>>().set(GameState::InGame);
+        app.update();
+
+        let center = grid.cell_center(cell);
+        for x in [-10., 10.] {
+            app.world_mut().spawn((
+                Ball,
+                Velocity(Vec3::new(0., BALL_SPEED, 0.)),
+                Transform::from_xyz(center.x + x, center.y - BRICK_HEIGHT / 2., 0.),
+            ));
+        }
+        app.update();
+
+        assert_eq!(app.world().resource::<Score>().0, BrickKind::Standard.points());
+        assert_eq!(app.world().resource::<Events<BrickDestroyed>>().len(), 1);
+        let mut bricks = app.world_mut().query_filtered::<(), With<Brick>>();
+        assert_eq!(bricks.iter(app.world()).count(), 0);
+    }
+
+    #[test]
+    fn a_launch_between_ticks_isnt_lost() {
+        let mut app = headless_app(BrickBreakerPlugin {
+            seed: Some(1),
+            level: None,
+        });
+        app.world_mut().resource_mut::<NextState<Screen>>().set(Screen::Playing);
+        app.update();
+
+        // Frames much shorter than a tick, so most of them don't run one
+        app.insert_resource(TimeUpdateStrategy::ManualDuration(TICK / 5));
+        app.world_mut().send_event(KeyboardInput {
+            key_code: KeyCode::Space,
+            logical_key: Key::Space,
+            state: ButtonState::Pressed,
+            window: Entity::PLACEHOLDER,
+        });
+        app.update();
+        app.world_mut().send_event(KeyboardInput {
+            key_code: KeyCode::Space,
+            logical_key: Key::Space,
+            state: ButtonState::Released,
+            window: Entity::PLACEHOLDER,
+        });
+        for _ in 0..10 {
+            app.update();
+        }
+
+        assert_eq!(*app.world().resource::<State<GameState>>().get(), GameState::InGame);
+    }
+}
@@ -1,35 +1,45 @@
-use bevy::{
-    prelude::*,
-    sprite::{MaterialMesh2dBundle, Mesh2dHandle},
-    math::bounding::{Aabb2d, BoundingCircle, BoundingVolume, IntersectsVolume},
-};
-
-mod components;
-
-use components::*;
-
-const SCREEN_WIDTH: f32 = 1280.;
-const SCREEN_HEIGHT: f32 = 720.;
-const PADDLE_WIDTH: f32 = 100.;
-const PADDLE_HEIGHT: f32 = 10.;
-const PADDLE_PAD: f32 = 30.;
-const PADDLE_SPEED: f32 = 500.;
-const BALL_RADIUS: f32 = 8.;
-const BALL_SPEED: f32 = 400.;
-const BRICK_WIDTH: f32 = 100.;
-const BRICK_HEIGHT: f32 = 30.;
-const BRICK_PAD_LR: f32 = 30.;
-const BRICK_PAD_TOP: f32 = 50.0;
-const BRICK_PAD_BOTTOM: f32 = 300.0;
-const BRICK_SPACE: f32 = 1.0;
+use std::path::PathBuf;
+
+use bevy::prelude::*;
+use common::settings::{Settings, SettingsPlugin};
+
+use brick_breaker::components::{Lives, Score, Screen};
+use brick_breaker::console::ConsolePlugin;
+use brick_breaker::controller::*;
+use brick_breaker::debug::DebugOverlayPlugin;
+use brick_breaker::editor::EditorPlugin;
+use brick_breaker::flow::GameFlowPlugin;
+use brick_breaker::generator::EndlessPlugin;
+use brick_breaker::highscore::{HighScorePlugin, HighScores};
+use brick_breaker::juice::{JuicePlugin, JuiceSettings};
+use brick_breaker::level::{Level, LevelGrid};
+use brick_breaker::particles::ParticlesPlugin;
+use brick_breaker::playfield::*;
+use brick_breaker::remote::RemoteControlPlugin;
+use brick_breaker::rewind::RewindPlugin;
+use brick_breaker::timescale::TimeScalePlugin;
+use brick_breaker::title::TitlePlugin;
+use brick_breaker::trail::TrailPlugin;
+use brick_breaker::BrickBreakerPlugin;
 
 fn main() {
-    App::new()
+    let playfield = Playfield::default();
+    let level = level_from_args(&playfield);
+    let endless = endless_seed();
+    // Each level, and endless mode, has its own high-score table
+    let mode = match (&level, endless) {
+        (_, Some(_)) => "Endless".to_string(),
+        (Some(level), None) => level.name.clone(),
+        (None, None) => "Classic".to_string(),
+    };
+
+    let mut app = App::new();
+    app
         .add_plugins(
             DefaultPlugins
                 .set(WindowPlugin {
                     primary_window: Some(Window {
-                        resolution: (SCREEN_WIDTH, SCREEN_HEIGHT).into(),
+                        resolution: (playfield.width, playfield.height).into(),
                         title: "Brick Breaker".to_string(),
                         ..default()
                     }),
@@ -37,244 +47,143 @@ fn main() {
                 })
                 .build(),
         )
-        .init_state::<GameState>()
-        .add_event::<CollisionEvent>()
-        .add_systems(Startup, setup)
-        .add_systems(Update, (player_input, update_position, check_collisions).chain())
-        .add_systems(Update, ball_follow.run_if(in_state(GameState::Start)))
-        .run();
-}
+        .add_plugins(BrickBreakerPlugin {
+            level,
+            ..default()
+        })
+        .add_plugins(TitlePlugin {
+            players: controller_setup,
+        })
+        .add_plugins(GameFlowPlugin)
+        .add_plugins(ParticlesPlugin)
+        .add_plugins(TrailPlugin)
+        .add_plugins(DebugOverlayPlugin)
+        .add_plugins(ConsolePlugin)
+        .add_plugins(TimeScalePlugin)
+        .add_plugins(RewindPlugin)
+        .add_plugins(SettingsPlugin {
+            path: Settings::default_path("brick_breaker"),
+            // Turns off screen shake and the other effects for this run
+            reduce_motion: std::env::args().any(|arg| arg == "--reduce-motion"),
+        })
+        .add_plugins(JuicePlugin {
+            settings: JuiceSettings::default(),
+        })
+        .add_plugins(HighScorePlugin {
+            path: HighScores::default_path("brick_breaker"),
+            mode,
+            // The autopilot's games don't count
+            record: !std::env::args().any(|arg| arg == "--demo"),
+        })
+        .add_systems(Startup, (spawn_camera, spawn_hud))
+        .add_systems(Update, (toggle_fullscreen, fit_camera_to_window).chain())
+        .add_systems(Update, update_hud);
+
+    // `--grab-cursor` hides the mouse cursor and keeps it in the window during games
+    if std::env::args().any(|arg| arg == "--grab-cursor") {
+        app.add_systems(OnEnter(Screen::Playing), grab_cursor)
+            .add_systems(OnExit(Screen::Playing), release_cursor);
+    }
 
-fn setup(
-    mut commands: Commands,
-    mut meshes: ResMut<Assets<Mesh>>,
-    mut materials: ResMut<Assets<ColorMaterial>>,
-) {
-    commands.spawn(Camera2dBundle::default());
+    if let Some(seed) = endless {
+        app.add_plugins(EndlessPlugin { seed });
+    }
 
-    // Paddle
-    commands.spawn((
-        MaterialMesh2dBundle {
-            mesh: Mesh2dHandle(meshes.add(Rectangle::new(1.0, 1.0))),
-            material: materials.add(Color::WHITE),
-            transform: Transform {
-                translation: Vec3::new(
-                    0.0,
-                    -SCREEN_HEIGHT / 2. + PADDLE_PAD,
-                    0.0
-                ),
-                scale: Vec3::new(PADDLE_WIDTH, PADDLE_HEIGHT, 1.0),
-                ..default()
-            },
-            ..default()
-        },
-        Paddle,
-        Collider,
-        Velocity(Vec3::new(0.0, 0.0, 0.0))
-    ));
+    if let Some(path) = editor_file() {
+        app.add_plugins(EditorPlugin { path });
+    }
 
-    // Ball
-    commands.spawn((
-        MaterialMesh2dBundle {
-            mesh: Mesh2dHandle(meshes.add(Circle {
-                radius: 1.0,
-            })),
-            material: materials.add(Color::WHITE),
-            transform: Transform::from_translation(Vec3::new(
-                0.0,
-                -SCREEN_HEIGHT / 2. + PADDLE_PAD + PADDLE_HEIGHT + BALL_RADIUS/2.,
-                0.0
-            )).with_scale(Vec2::splat(BALL_RADIUS).extend(1.)),
-            ..default()
-        },
-        Ball,
-        Velocity(Vec3::new(0.0, 0.0, 0.0))
-    ));
+    if let Some(port) = remote_control_port() {
+        app.add_plugins(RemoteControlPlugin { port });
+    }
 
-    let brick_cols = ((SCREEN_WIDTH - BRICK_PAD_LR * 2.) / (BRICK_WIDTH + BRICK_SPACE*2.) as f32).floor();
-    let brick_rows = ((SCREEN_HEIGHT - BRICK_PAD_TOP - BRICK_PAD_BOTTOM) / (BRICK_HEIGHT + BRICK_SPACE*2.) as f32).floor();
+    app.run();
+}
 
-    let left_over = SCREEN_WIDTH - brick_cols * (BRICK_WIDTH + BRICK_SPACE*2.);
-    let start_x = -SCREEN_WIDTH / 2. + left_over / 2.;
+// `--remote-control <port>` lets bots drive the paddle over a localhost socket
+fn remote_control_port() -> Option<u16> {
+    let mut args = std::env::args().skip_while(|arg| arg != "--remote-control");
+    args.next()?;
+    let port = args.next().expect("--remote-control needs a port number");
+    Some(port.parse().expect("--remote-control port must be a number between 0 and 65535"))
+}
 
-    // Bricks
-    for i in 0..brick_rows as i32 {
-        for j in 0..=brick_cols as i32 {
-            let x = start_x + j as f32 * (BRICK_WIDTH + BRICK_SPACE);
-            let y = -SCREEN_HEIGHT / 2. + BRICK_PAD_BOTTOM + BRICK_HEIGHT / 2. + i as f32 * (BRICK_HEIGHT + BRICK_SPACE*2.);
-            commands.spawn((
-                MaterialMesh2dBundle {
-                    mesh: Mesh2dHandle(meshes.add(Rectangle::new(1.0, 1.0))),
-                    material: materials.add(Color::WHITE),
-                    transform: Transform {
-                        translation: Vec3::new(
-                            x,
-                            y,
-                            0.0
-                        ),
-                        scale: Vec3::new(BRICK_WIDTH, BRICK_HEIGHT, 1.0),
-                        ..default()
-                    },
-                    ..default()
-                },
-                Brick,
-                Collider,
-            ));
-        }
+// `--endless [seed]` plays generated levels one after another, from a random seed if none is given
+fn endless_seed() -> Option<u64> {
+    let mut args = std::env::args().skip_while(|arg| arg != "--endless").peekable();
+    args.next()?;
+    match args.next_if(|arg| !arg.starts_with("--")) {
+        Some(seed) => Some(seed.parse().expect("--endless seed must be a whole number")),
+        None => Some(rand::random()),
     }
 }
 
-fn player_input(
-    key: Res<ButtonInput<KeyCode>>,
-    mut query: Query<&mut Velocity, With<Paddle>>,
-    mut ball_query: Query<&mut Velocity, (With<Ball>, Without<Paddle>)>,
-    turn_state: ResMut<State<GameState>>,
-    mut next_state: ResMut<NextState<GameState>>,
-) {
-    for mut velocity in query.iter_mut() {
-        if key.pressed(KeyCode::KeyA) {
-            velocity.0.x = -PADDLE_SPEED;
-        } else if key.pressed(KeyCode::KeyD) {
-            velocity.0.x = PADDLE_SPEED;
-        } else {
-            velocity.0.x = 0.;
-        }
-    }
+// `--edit <file>` starts in the level editor, working on the file
+fn editor_file() -> Option<PathBuf> {
+    let mut args = std::env::args().skip_while(|arg| arg != "--edit");
+    args.next()?;
+    Some(args.next().expect("--edit needs a level file").into())
+}
 
-    if turn_state.get() == &GameState::Start {
-        if key.just_pressed(KeyCode::Space) {
-            let mut ball_velocity = ball_query.single_mut();
-            ball_velocity.0 = Vec3::new(BALL_SPEED, BALL_SPEED, 0.0);
-            ball_velocity.0 = ball_velocity.0.normalize() * BALL_SPEED;
-            next_state.set(GameState::InGame);
-        }
+// `--level <file>` plays a level file instead of the classic layout
+fn level_from_args(playfield: &Playfield) -> Option<Level> {
+    let mut args = std::env::args().skip_while(|arg| arg != "--level");
+    args.next()?;
+    let path = args.next().expect("--level needs a level file");
+    let level = Level::load(path.as_ref()).unwrap_or_else(|error| panic!("{}", error));
+    if let Err(error) = level.validate(&LevelGrid::new(playfield)) {
+        panic!("{} can't be played: {}", path, error);
     }
+    Some(level)
 }
 
-fn update_position(time: Res<Time>, mut query: Query<(&Velocity, &mut Transform)>) {
-    for (velocity, mut transform) in query.iter_mut() {
-        transform.translation += velocity.0 * time.delta_seconds();
+// Who plays the paddle: the keyboard by default, `--mouse` to follow the mouse, `--gamepad`
+// for the first gamepad, or `--demo` to watch the autopilot
+fn controller_setup() -> ControllerSetup {
+    let flag = |name: &str| std::env::args().any(|arg| arg == name);
+
+    if flag("--demo") {
+        ControllerSetup::new(Autopilot)
+    } else if flag("--mouse") {
+        ControllerSetup::new(Mouse)
+    } else if flag("--gamepad") {
+        ControllerSetup::new(GamepadStick(0))
+    } else {
+        ControllerSetup::default()
     }
 }
 
-fn ball_follow(
-    mut ball_query: Query<&mut Transform, With<Ball>>,
-    paddle_query: Query<&Transform, (With<Paddle>, Without<Ball>)>,
-) {
-    // This system only runs if the game state is Start
-
-    let mut ball_transform = ball_query.single_mut();
-    let paddle_transform = paddle_query.single();
 
-    ball_transform.translation.x = paddle_transform.translation.x;
+fn spawn_camera(mut commands: Commands) {
+    commands.spawn(Camera2dBundle::default());
 }
 
-fn check_collisions(
-    mut commands: Commands,
-    mut ball_query: Query<(&mut Velocity, &mut Transform), (With<Ball>, Without<Paddle>)>,
-    mut collider_query: Query<(Entity, &mut Transform, Option<&Brick>, Option<&Paddle>, Option<&mut Velocity>), (With<Collider>, Without<Ball>)>,
-    mut collision_events: EventWriter<CollisionEvent>,
-) {
-    let (mut ball_velocity, mut ball_transform) = ball_query.single_mut();
-    for (entity, mut transform, maybe_brick, maybe_paddle, maybe_velocity) in collider_query.iter_mut() {
-        if maybe_paddle.is_some() {
-            let mut velocity = maybe_velocity.unwrap();
-            if transform.translation.x - PADDLE_WIDTH/2. < -SCREEN_WIDTH/2. {
-                // Clamp left wall
-                velocity.0.x = 0.;
-                transform.translation.x = -SCREEN_WIDTH/2. + PADDLE_WIDTH/2. + 1.
-            }
-            if transform.translation.x + PADDLE_WIDTH/2. > SCREEN_WIDTH/2. {
-                // Clamp right wall
-                velocity.0.x = 0.;
-                transform.translation.x = SCREEN_WIDTH/2. - PADDLE_WIDTH/2. - 1.
-            }
-        }
-
-
-        let collision = ball_collision(
-            BoundingCircle::new(ball_transform.translation.truncate(), BALL_RADIUS),
-            Aabb2d::new(
-                transform.translation.truncate(),
-                transform.scale.truncate() / 2.,
-            ),
-        );
-
-        if let Some(collision) = collision {
-            // Sends a collision event so that other systems can react to the collision
-            collision_events.send_default();
-
-            // Bricks should be despawned and increment the scoreboard on collision
-            if maybe_brick.is_some() {
-                commands.entity(entity).despawn();
-                // **score += 1;
-            }
+#[derive(Component)]
+struct Hud;
 
-            // Reflect the ball's velocity when it collides
-            let mut reflect_x = false;
-            let mut reflect_y = false;
-
-            // Reflect only if the velocity is in the opposite direction of the collision
-            // This prevents the ball from getting stuck inside the bar
-            match collision {
-                Collision::Left => reflect_x = ball_velocity.0.x > 0.0,
-                Collision::Right => reflect_x = ball_velocity.0.x < 0.0,
-                Collision::Top => reflect_y = ball_velocity.0.y < 0.0,
-                Collision::Bottom => reflect_y = ball_velocity.0.y > 0.0,
-            }
-
-            // Reflect velocity on the x-axis if we hit something on the x-axis
-            if reflect_x {
-                ball_velocity.0.x = -ball_velocity.0.x;
-            }
+fn spawn_hud(mut commands: Commands) {
+    commands.spawn((
+        TextBundle::from_section(
+            "",
+            TextStyle {
+                font_size: 24.0,
+                ..default()
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            top: Val::Px(10.0),
+            left: Val::Px(10.0),
+            ..default()
+        }),
+        Hud,
+    ));
+}
 
-            // Reflect velocity on the y-axis if we hit something on the y-axis
-            if reflect_y {
-                ball_velocity.0.y = -ball_velocity.0.y;
-            }
+fn update_hud(score: Res<Score>, lives: Res<Lives>, mut query: Query<&mut Text, With<Hud>>) {
+    if score.is_changed() || lives.is_changed() {
+        for mut text in query.iter_mut() {
+            text.sections[0].value = format!("Score: {}   Balls: {}", score.0, lives.0);
         }
     }
-
-    if ball_transform.translation.y - BALL_RADIUS < -SCREEN_HEIGHT/2. {
-        // Bounce off the top wall
-        ball_velocity.0.y = -ball_velocity.0.y;
-        ball_transform.translation.y = -SCREEN_HEIGHT/2. + BALL_RADIUS + 1.;
-    }
-    if ball_transform.translation.y + BALL_RADIUS > SCREEN_HEIGHT/2. {
-        // Bounce off the bottom wall
-        ball_velocity.0.y = -ball_velocity.0.y;
-        ball_transform.translation.y = SCREEN_HEIGHT/2. - BALL_RADIUS - 1.;
-    }
-    if ball_transform.translation.x - BALL_RADIUS < -SCREEN_WIDTH/2. {
-        // Bounce off the left wall
-        ball_velocity.0.x = -ball_velocity.0.x;
-        ball_transform.translation.x = -SCREEN_WIDTH/2. + BALL_RADIUS + 1.;
-    }
-    if ball_transform.translation.x + BALL_RADIUS > SCREEN_WIDTH/2. {
-        // Bounce off the right wall
-        ball_velocity.0.x = -ball_velocity.0.x;
-        ball_transform.translation.x = SCREEN_WIDTH/2. - BALL_RADIUS - 1.;
-    }
 }
-
-fn ball_collision(ball: BoundingCircle, bounding_box: Aabb2d) -> Option<Collision> {
-    if !ball.intersects(&bounding_box) {
-        return None;
-    }
-
-    let closest = bounding_box.closest_point(ball.center());
-    let offset = ball.center() - closest;
-    let side = if offset.x.abs() > offset.y.abs() {
-        if offset.x < 0. {
-            Collision::Left
-        } else {
-            Collision::Right
-        }
-    } else if offset.y > 0. {
-        Collision::Top
-    } else {
-        Collision::Bottom
-    };
-
-    Some(side)
-}
\ No newline at end of file
@@ -0,0 +1,74 @@
+use std::f32::consts::FRAC_PI_3;
+
+use bevy::prelude::*;
+use common::particles::{Burst, ParticleSystems, Particles};
+
+use crate::components::Ball;
+use crate::events::{BallHitPaddle, BrickDamaged, BrickDestroyed};
+use crate::{BALL_RADIUS, BRICK_HEIGHT, BRICK_WIDTH};
+
+// Debris from broken bricks and sparks off the paddle
+pub struct ParticlesPlugin;
+
+impl Plugin for ParticlesPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins(common::particles::ParticlesPlugin)
+            .add_systems(Update, (emit_brick_debris, emit_paddle_sparks).before(ParticleSystems));
+    }
+}
+
+// Broken bricks burst into pieces of their own color, and damaged ones chip
+fn emit_brick_debris(
+    mut destroyed: EventReader<BrickDestroyed>,
+    mut damaged: EventReader<BrickDamaged>,
+    mut particles: ResMut<Particles>,
+) {
+    for event in destroyed.read() {
+        particles.burst(Burst {
+            position: event.position,
+            area: Vec2::new(BRICK_WIDTH, BRICK_HEIGHT),
+            count: 40,
+            speed: 250.,
+            lifetime: 0.9,
+            size: 6.,
+            gravity: -700.,
+            color: event.color,
+            ..default()
+        });
+    }
+    for event in damaged.read() {
+        particles.burst(Burst {
+            position: event.position,
+            area: Vec2::new(BRICK_WIDTH, BRICK_HEIGHT) / 2.,
+            count: 8,
+            speed: 150.,
+            lifetime: 0.5,
+            size: 4.,
+            gravity: -700.,
+            color: event.color,
+            ..default()
+        });
+    }
+}
+
+fn emit_paddle_sparks(
+    mut hits: EventReader<BallHitPaddle>,
+    balls: Query<&Transform, With<Ball>>,
+    mut particles: ResMut<Particles>,
+) {
+    for event in hits.read() {
+        let Ok(ball) = balls.get(event.ball) else {
+            continue;
+        };
+        particles.burst(Burst {
+            position: ball.translation.truncate() - Vec2::new(0., BALL_RADIUS),
+            spread: FRAC_PI_3,
+            count: 20,
+            speed: 400.,
+            lifetime: 0.35,
+            size: 3.,
+            color: Color::srgb(1.0, 0.85, 0.4),
+            ..default()
+        });
+    }
+}
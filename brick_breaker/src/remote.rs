@@ -0,0 +1,173 @@
+use bevy::prelude::*;
+use common::remote::{RemotePaddle, RemoteServer};
+use serde::{Deserialize, Serialize};
+
+use crate::components::{Ball, Brick, GameState, Paddle, Velocity};
+use crate::controller::{drive_paddle, Controller, PaddleCommand, PaddleView};
+
+// Opens a localhost TCP port that bots can connect to. Every frame each client is sent
+// the game state as a line of JSON:
+//
+//   {"frame": 120, "state": "in_game", "paddle": {...}, "balls": [...], "bricks_left": 42}
+//
+// where "state" is "title" (with no paddle), "start", "in_game", "paused", "rewinding",
+// "level_complete" or "game_over". Clients can send back lines like {"paddle": "left"} or
+// {"launch": true} to take over the paddle, which goes back to its own controller once
+// every client disconnects.
+pub struct RemoteControlPlugin {
+    pub port: u16,
+}
+
+impl Plugin for RemoteControlPlugin {
+    fn build(&self, app: &mut App) {
+        let server = RemoteServer::bind(self.port)
+            .unwrap_or_else(|error| panic!("Couldn't open remote control port {}: {}", self.port, error));
+        info!("Remote control listening on {}", server.local_addr().unwrap());
+
+        app.insert_resource(RemoteControl {
+            server,
+            frame: 0,
+            paddle: RemotePaddle::default(),
+        })
+        .init_resource::<RemoteCommands>()
+        .add_systems(
+            Update,
+            (accept_remote_clients, read_remote_commands, apply_remote_commands)
+                .chain()
+                .before(drive_paddle),
+        )
+        .add_systems(PostUpdate, send_remote_state);
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum PaddleAction {
+    Left,
+    Right,
+    Stay,
+}
+
+#[derive(Resource)]
+struct RemoteControl {
+    server: RemoteServer,
+    frame: u64,
+    paddle: RemotePaddle<PaddleView, PaddleCommand>,
+}
+
+// The latest paddle command, `None` if nobody is driving it remotely
+#[derive(Resource, Default, Deserialize)]
+struct RemoteCommands {
+    paddle: Option<PaddleAction>,
+    #[serde(default)]
+    launch: bool,
+}
+
+#[derive(Serialize)]
+struct RemoteState {
+    frame: u64,
+    state: &'static str,
+    // Missing on the title screen
+    paddle: Option<PaddleState>,
+    balls: Vec<BallState>,
+    bricks_left: usize,
+}
+
+#[derive(Serialize)]
+struct PaddleState {
+    x: f32,
+    velocity: f32,
+}
+
+#[derive(Serialize)]
+struct BallState {
+    position: [f32; 2],
+    velocity: [f32; 2],
+}
+
+fn accept_remote_clients(mut remote: ResMut<RemoteControl>, mut commands: ResMut<RemoteCommands>) {
+    remote.server.accept();
+    if !remote.server.has_clients() {
+        *commands = RemoteCommands::default();
+    }
+}
+
+fn read_remote_commands(mut remote: ResMut<RemoteControl>, mut commands: ResMut<RemoteCommands>) {
+    for command in remote.server.receive::<RemoteCommands>() {
+        commands.paddle = command.paddle.or(commands.paddle);
+        commands.launch |= command.launch;
+    }
+}
+
+fn apply_remote_commands(
+    mut remote: ResMut<RemoteControl>,
+    mut remote_commands: ResMut<RemoteCommands>,
+    mut paddle_query: Query<(Entity, Option<&mut Controller>), With<Paddle>>,
+    mut commands: Commands,
+) {
+    let launch = std::mem::take(&mut remote_commands.launch);
+    let command = match remote_commands.paddle {
+        Some(action) => Some(PaddleCommand {
+            steer: match action {
+                PaddleAction::Left => -1.,
+                PaddleAction::Right => 1.,
+                PaddleAction::Stay => 0.,
+            },
+            launch,
+            follow: None,
+        }),
+        None if launch => Some(PaddleCommand {
+            steer: 0.,
+            launch,
+            follow: None,
+        }),
+        // Keep the paddle until the bots are gone, even if they've only ever sent launches
+        None if remote.paddle.is_taken_over() && remote.server.has_clients() => Some(PaddleCommand::default()),
+        None => None,
+    };
+
+    // Nothing to drive on the title screen
+    let Ok((paddle, controller)) = paddle_query.get_single_mut() else {
+        return;
+    };
+    remote.paddle.steer(command, paddle, controller, &mut commands);
+}
+
+fn send_remote_state(
+    mut remote: ResMut<RemoteControl>,
+    paddle_query: Query<(&Transform, &Velocity), With<Paddle>>,
+    ball_query: Query<(&Transform, &Velocity), With<Ball>>,
+    brick_query: Query<(), With<Brick>>,
+    state: Option<Res<State<GameState>>>,
+) {
+    remote.frame += 1;
+    if !remote.server.has_clients() {
+        return;
+    }
+
+    let state = RemoteState {
+        frame: remote.frame,
+        state: match state.as_deref().map(State::get) {
+            None => "title",
+            Some(GameState::Start) => "start",
+            Some(GameState::InGame) => "in_game",
+            Some(GameState::Paused) => "paused",
+            Some(GameState::Rewinding) => "rewinding",
+            Some(GameState::LevelComplete) => "level_complete",
+            Some(GameState::GameOver) => "game_over",
+        },
+        paddle: paddle_query.get_single().ok().map(|(transform, velocity)| PaddleState {
+            x: transform.translation.x,
+            velocity: velocity.0.x,
+        }),
+        balls: ball_query
+            .iter()
+            .map(|(transform, velocity)| BallState {
+                position: transform.translation.truncate().to_array(),
+                velocity: velocity.0.truncate().to_array(),
+            })
+            .collect(),
+        bricks_left: brick_query.iter().count(),
+    };
+    remote.server.send(&state);
+}
@@ -0,0 +1,397 @@
+use std::collections::VecDeque;
+use std::time::Duration;
+
+use bevy::prelude::*;
+use rand::rngs::StdRng;
+
+use crate::assets::GameAssets;
+use crate::components::{Ball, BallId, GameRng, GameState, GridCell, Health, Lives, Paddle, Score, Screen, Velocity};
+use crate::level::Level;
+use crate::playfield::Playfield;
+use crate::{check_level_complete, spawn_ball, spawn_bricks};
+
+// How far back play can be rewound, in game time
+pub const REWIND_TIME: Duration = Duration::from_secs(5);
+
+// Holding F9 runs the game backwards, a tick at a time, through the last few seconds of
+// play, and letting go carries on playing from there. It works from the level complete and
+// game over screens too, for another go at the end of a level. Every tick of play is
+// snapshotted into a ring buffer, which starts over with every new level or restart.
+// Snapshots are stamped with how much game time had been played, so the buffer covers the
+// same stretch of play, and a rewind runs back through it at the same pace, whatever the
+// frame rate or time scale.
+//
+// A snapshot is the state of play at the end of a tick, the gameplay randomness included,
+// which is enough to carry on playing from. It isn't a replay: the controllers aren't
+// snapshotted, so play from there won't go the way it did before.
+pub struct RewindPlugin;
+
+impl Plugin for RewindPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<History>()
+            .add_systems(OnEnter(GameState::Rewinding), spawn_rewind_text)
+            .add_systems(
+                FixedUpdate,
+                (
+                    number_balls,
+                    forget_history,
+                    take_snapshot
+                        .after(check_level_complete)
+                        .run_if(in_state(GameState::Start).or_else(in_state(GameState::InGame))),
+                    rewind.run_if(in_state(GameState::Rewinding)),
+                )
+                    .chain(),
+            )
+            .add_systems(
+                Update,
+                start_rewind.run_if(
+                    in_state(GameState::Start)
+                        .or_else(in_state(GameState::InGame))
+                        .or_else(in_state(GameState::LevelComplete))
+                        .or_else(in_state(GameState::GameOver)),
+                ),
+            );
+    }
+}
+
+// The game as it was at the end of a tick
+#[derive(Clone)]
+pub struct Snapshot {
+    // Game time played up to this tick, not counting time spent frozen or rewinding
+    pub time: Duration,
+    // Position and velocity of each ball
+    pub balls: Vec<(BallId, Vec3, Vec3)>,
+    pub paddle: (Vec3, Vec3),
+    // Health of each brick that can still be broken
+    pub bricks: Vec<(GridCell, u32)>,
+    pub score: u32,
+    pub lives: u32,
+    pub rng: StdRng,
+    // Whether the ball was sitting on the paddle waiting to be launched
+    pub serving: bool,
+}
+
+// The most recent snapshots, oldest first
+#[derive(Resource, Default)]
+pub struct History(pub VecDeque<Snapshot>);
+
+// Every ball gets the next id, and a ball put back by a rewind keeps the one it had
+fn number_balls(mut commands: Commands, new_balls: Query<Entity, (With<Ball>, Without<BallId>)>, mut next: Local<u32>) {
+    for ball in new_balls.iter() {
+        commands.entity(ball).insert(BallId(*next));
+        *next += 1;
+    }
+}
+
+// Earlier snapshots are of a game that's been thrown away
+fn forget_history(new_game: Query<(), Added<Paddle>>, level: Res<Level>, mut history: ResMut<History>) {
+    if !new_game.is_empty() || level.is_changed() {
+        history.0.clear();
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn take_snapshot(
+    time: Res<Time>,
+    ball_query: Query<(&BallId, &Transform, &Velocity), With<Ball>>,
+    paddle_query: Query<(&Transform, &Velocity), With<Paddle>>,
+    brick_query: Query<(&GridCell, &Health)>,
+    score: Res<Score>,
+    lives: Res<Lives>,
+    rng: Res<GameRng>,
+    state: Res<State<GameState>>,
+    mut history: ResMut<History>,
+) {
+    let Ok((paddle_transform, paddle_velocity)) = paddle_query.get_single() else {
+        return;
+    };
+
+    // Play carries on from the latest snapshot, which after a rewind is the one it went back to
+    let played = history.0.back().map_or(Duration::ZERO, |snapshot| snapshot.time) + time.delta();
+    while history.0.front().is_some_and(|oldest| oldest.time + REWIND_TIME < played) {
+        history.0.pop_front();
+    }
+    history.0.push_back(Snapshot {
+        time: played,
+        balls: ball_query
+            .iter()
+            .map(|(id, transform, velocity)| (*id, transform.translation, velocity.0))
+            .collect(),
+        paddle: (paddle_transform.translation, paddle_velocity.0),
+        bricks: brick_query.iter().map(|(cell, health)| (*cell, health.0)).collect(),
+        score: score.0,
+        lives: lives.0,
+        rng: rng.0.clone(),
+        serving: *state.get() == GameState::Start,
+    });
+}
+
+fn start_rewind(
+    keys: Res<ButtonInput<KeyCode>>,
+    history: Res<History>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    if keys.pressed(KeyCode::F9) && !history.0.is_empty() {
+        next_state.set(GameState::Rewinding);
+    }
+}
+
+// Steps back as much game time each tick as the tick took while F9 is held, stopping at
+// the oldest snapshot, and carries on from whichever one it's on when it's let go. Leaving the
+// level complete or game over screen for this takes their overlay with it
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
+fn rewind(
+    mut commands: Commands,
+    time: Res<Time>,
+    keys: Res<ButtonInput<KeyCode>>,
+    mut history: ResMut<History>,
+    mut assets: ResMut<GameAssets>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    playfield: Res<Playfield>,
+    level: Res<Level>,
+    screen: Res<State<Screen>>,
+    mut ball_query: Query<(Entity, &BallId, &mut Transform, &mut Velocity), (With<Ball>, Without<Paddle>)>,
+    mut paddle_query: Query<(&mut Transform, &mut Velocity), (With<Paddle>, Without<Ball>)>,
+    mut brick_query: Query<(&GridCell, &mut Health)>,
+    mut score: ResMut<Score>,
+    mut lives: ResMut<Lives>,
+    mut rng: ResMut<GameRng>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    let rewinding = keys.pressed(KeyCode::F9);
+    if let Some(latest) = history.0.back().filter(|_| rewinding) {
+        let back_to = latest.time.saturating_sub(time.delta());
+        while history.0.len() > 1 && history.0.back().is_some_and(|snapshot| snapshot.time > back_to) {
+            history.0.pop_back();
+        }
+    }
+    let Some(snapshot) = history.0.back() else {
+        next_state.set(GameState::Start);
+        return;
+    };
+
+    // Balls come and go as they're lost or added, so each is matched up by its id
+    for (entity, id, mut transform, mut velocity) in ball_query.iter_mut() {
+        match snapshot.balls.iter().find(|(ball, _, _)| ball == id) {
+            Some(&(_, position, ball_velocity)) => {
+                transform.translation = position;
+                velocity.0 = ball_velocity;
+            }
+            None => commands.entity(entity).despawn(),
+        }
+    }
+    for &(id, position, velocity) in &snapshot.balls {
+        if !ball_query.iter().any(|(_, ball, _, _)| *ball == id) {
+            let ball = spawn_ball(&mut commands, &mut assets, &mut materials, position, velocity, *screen.get());
+            commands.entity(ball).insert(id);
+        }
+    }
+
+    if let Ok((mut transform, mut velocity)) = paddle_query.get_single_mut() {
+        transform.translation = snapshot.paddle.0;
+        velocity.0 = snapshot.paddle.1;
+    }
+
+    // Broken bricks are put back at full health, and have it set properly the next time round
+    let mut broken = Level::empty(&level.name);
+    for &(cell, hits) in &snapshot.bricks {
+        match brick_query.iter_mut().find(|(brick_cell, _)| **brick_cell == cell) {
+            Some((_, mut health)) => health.0 = hits,
+            None => broken.bricks.extend(level.bricks.iter().filter(|brick| brick.cell() == cell)),
+        }
+    }
+    spawn_bricks(&mut commands, &mut assets, &mut materials, &playfield, &broken, *screen.get());
+
+    score.0 = snapshot.score;
+    lives.0 = snapshot.lives;
+    rng.0 = snapshot.rng.clone();
+
+    if !rewinding && broken.bricks.is_empty() {
+        next_state.set(if snapshot.serving { GameState::Start } else { GameState::InGame });
+    }
+}
+
+fn spawn_rewind_text(mut commands: Commands) {
+    commands.spawn((
+        TextBundle::from_section(
+            "<< REWIND",
+            TextStyle {
+                font_size: 40.0,
+                ..default()
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            top: Val::Px(40.0),
+            left: Val::Px(10.0),
+            ..default()
+        }),
+        StateScoped(GameState::Rewinding),
+    ));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::flow::GameFlowPlugin;
+    use crate::{headless_app, BrickBreakerPlugin, BALL_SPEED, HEADLESS_FRAME};
+    use bevy::time::TimeUpdateStrategy;
+
+    // A game on the playing screen with the ball launched straight up, so it bounces between
+    // the paddle and the bricks without ever being lost
+    fn game() -> App {
+        let mut app = headless_app(BrickBreakerPlugin {
+            seed: Some(1),
+            level: None,
+        });
+        app.add_plugins((RewindPlugin, GameFlowPlugin));
+        app.world_mut().resource_mut::<NextState<Screen>>().set(Screen::Playing);
+        app.update();
+
+        let mut balls = app.world_mut().query_filtered::<&mut Velocity, With<Ball>>();
+        for mut velocity in balls.iter_mut(app.world_mut()) {
+            velocity.0 = Vec3::new(0., BALL_SPEED, 0.);
+        }
+        app.world_mut().resource_mut::<NextState<GameState>>().set(GameState::InGame);
+        app.update();
+        app
+    }
+
+    fn run(app: &mut App, frames: u32) {
+        for _ in 0..frames {
+            app.update();
+        }
+    }
+
+    fn hold_f9(app: &mut App, held: bool) {
+        let mut keys = app.world_mut().resource_mut::<ButtonInput<KeyCode>>();
+        if held {
+            keys.press(KeyCode::F9);
+        } else {
+            keys.release(KeyCode::F9);
+        }
+    }
+
+    fn state(app: &App) -> GameState {
+        *app.world().resource::<State<GameState>>().get()
+    }
+
+    // Every ball's id and position, by id
+    fn balls(app: &mut App) -> Vec<(BallId, Vec3)> {
+        let mut query = app.world_mut().query_filtered::<(&BallId, &Transform), With<Ball>>();
+        let mut balls: Vec<_> = query.iter(app.world()).map(|(id, transform)| (*id, transform.translation)).collect();
+        balls.sort_by_key(|(id, _)| id.0);
+        balls
+    }
+
+    fn latest_snapshot(app: &App) -> Vec<(BallId, Vec3)> {
+        let snapshot = app.world().resource::<History>().0.back().unwrap().clone();
+        let mut balls: Vec<_> = snapshot.balls.iter().map(|&(id, position, _)| (id, position)).collect();
+        balls.sort_by_key(|(id, _)| id.0);
+        balls
+    }
+
+    // Lets go of F9 and waits for play to carry on
+    fn resume(app: &mut App) {
+        hold_f9(app, false);
+        for _ in 0..5 {
+            app.update();
+            if state(app) != GameState::Rewinding {
+                return;
+            }
+        }
+        panic!("still rewinding after F9 was let go");
+    }
+
+    // Game time between the oldest and latest snapshots
+    fn history_span(app: &App) -> Duration {
+        let history = &app.world().resource::<History>().0;
+        history.back().unwrap().time - history.front().unwrap().time
+    }
+
+    #[test]
+    fn history_keeps_only_the_latest_snapshots() {
+        let mut app = game();
+        run(&mut app, 360);
+        assert!(history_span(&app) <= REWIND_TIME);
+        assert!(history_span(&app) > REWIND_TIME - HEADLESS_FRAME);
+    }
+
+    #[test]
+    fn history_covers_the_same_time_at_any_frame_rate() {
+        let mut app = game();
+        app.insert_resource(TimeUpdateStrategy::ManualDuration(HEADLESS_FRAME / 2));
+        run(&mut app, 720);
+        assert!(history_span(&app) <= REWIND_TIME);
+        assert!(history_span(&app) > REWIND_TIME - HEADLESS_FRAME);
+
+        // Going back a second takes a second, however many frames that is
+        hold_f9(&mut app, true);
+        run(&mut app, 1);
+        let latest = app.world().resource::<History>().0.back().unwrap().time;
+        run(&mut app, 120);
+        let rewound = app.world().resource::<History>().0.back().unwrap().time;
+        assert_eq!(latest - rewound, HEADLESS_FRAME * 60);
+    }
+
+    #[test]
+    fn rewinding_puts_the_game_back_and_carries_on_from_there() {
+        let mut app = game();
+        run(&mut app, 60);
+        let before = balls(&mut app);
+
+        hold_f9(&mut app, true);
+        run(&mut app, 30);
+        assert_eq!(state(&app), GameState::Rewinding);
+        let rewound = balls(&mut app);
+        assert_eq!(rewound, latest_snapshot(&app));
+        assert_ne!(rewound, before);
+
+        resume(&mut app);
+        assert_eq!(state(&app), GameState::InGame);
+        run(&mut app, 1);
+        assert_ne!(balls(&mut app), rewound);
+    }
+
+    #[test]
+    fn balls_are_put_back_by_id() {
+        let mut app = game();
+        run(&mut app, 10);
+        app.world_mut().spawn((
+            Ball,
+            Velocity(Vec3::new(BALL_SPEED, 0., 0.)),
+            Transform::from_xyz(-100., 0., 0.),
+            StateScoped(Screen::Playing),
+        ));
+        run(&mut app, 10);
+
+        // The first ball goes, leaving the new one as the only ball in play
+        let mut ids = app.world_mut().query::<(Entity, &BallId)>();
+        let (first, _) = ids.iter(app.world()).find(|(_, id)| id.0 == 0).unwrap();
+        app.world_mut().despawn(first);
+
+        hold_f9(&mut app, true);
+        run(&mut app, 2);
+        let rewound = balls(&mut app);
+        assert_eq!(rewound.len(), 2);
+        assert_eq!(rewound, latest_snapshot(&app));
+    }
+
+    #[test]
+    fn game_over_can_be_rewound() {
+        let mut app = game();
+        run(&mut app, 30);
+        app.world_mut().resource_mut::<NextState<GameState>>().set(GameState::GameOver);
+        app.update();
+        let mut overlay = app.world_mut().query::<&StateScoped<GameState>>();
+        assert!(overlay.iter(app.world()).any(|scope| scope.0 == GameState::GameOver));
+
+        hold_f9(&mut app, true);
+        run(&mut app, 2);
+        assert_eq!(state(&app), GameState::Rewinding);
+        assert!(!overlay.iter(app.world()).any(|scope| scope.0 == GameState::GameOver));
+
+        resume(&mut app);
+        assert_eq!(state(&app), GameState::InGame);
+    }
+}
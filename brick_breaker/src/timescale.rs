@@ -0,0 +1,11 @@
+use bevy::prelude::*;
+
+pub use common::timescale::{TimeScale, TIME_SCALES};
+
+pub struct TimeScalePlugin;
+
+impl Plugin for TimeScalePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins(common::timescale::TimeScalePlugin);
+    }
+}
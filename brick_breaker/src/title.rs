@@ -0,0 +1,36 @@
+use bevy::prelude::*;
+use common::title::TitleScreenPlugin;
+
+use crate::components::Screen;
+use crate::controller::{Autopilot, ControllerSetup};
+use crate::spawn_level;
+
+pub use common::title::AnyInput;
+
+// The title screen, with the autopilot playing the demo games
+pub struct TitlePlugin {
+    // Who plays when a game is started from the title screen
+    pub players: fn() -> ControllerSetup,
+}
+
+impl Plugin for TitlePlugin {
+    fn build(&self, app: &mut App) {
+        let players = self.players;
+
+        app.add_plugins(TitleScreenPlugin::<Screen>::new("BRICK BREAKER", 100.))
+            .add_systems(
+                OnEnter(Screen::Demo),
+                (|mut commands: Commands| {
+                    commands.insert_resource(ControllerSetup::new(Autopilot));
+                })
+                .before(spawn_level),
+            )
+            .add_systems(
+                OnEnter(Screen::Playing),
+                (move |mut commands: Commands| {
+                    commands.insert_resource(players());
+                })
+                .before(spawn_level),
+            );
+    }
+}
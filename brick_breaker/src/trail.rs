@@ -0,0 +1,23 @@
+use std::marker::PhantomData;
+
+use bevy::prelude::*;
+use common::trail::TrailStyle;
+
+use crate::components::Ball;
+use crate::{BALL_RADIUS, BALL_SPEED};
+
+// Trails behind the balls, at their hottest at twice the usual speed
+pub struct TrailPlugin;
+
+impl Plugin for TrailPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins(common::trail::TrailPlugin::<Ball> {
+            style: TrailStyle {
+                radius: BALL_RADIUS,
+                speed: BALL_SPEED,
+                hot_speed: BALL_SPEED * 2.,
+            },
+            balls: PhantomData,
+        });
+    }
+}
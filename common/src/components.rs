@@ -0,0 +1,15 @@
+use bevy::prelude::*;
+
+#[derive(Component)]
+pub struct Velocity(pub Vec3);
+
+// Where the ball touched something and the direction it was pushed off in
+#[derive(Debug, Copy, Clone)]
+pub struct Contact {
+    pub point: Vec2,
+    pub normal: Vec2,
+}
+
+// The ball's most recent contact, for the debug overlay
+#[derive(Resource, Default)]
+pub struct LastContact(pub Option<Contact>);
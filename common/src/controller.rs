@@ -0,0 +1,77 @@
+use std::marker::PhantomData;
+use std::sync::{Arc, Mutex};
+
+use bevy::{ecs::system::SystemParam, prelude::*, window::PrimaryWindow};
+
+use crate::playfield::cursor_position;
+
+// Anything that can play a paddle: a person on the keyboard, the mouse or a gamepad, a
+// built-in AI, a recording, or a bot talking to the game from outside. Each game decides
+// what a controller gets to see of it (`View`) and what it can tell its paddle (`Command`)
+pub trait PaddleController: Send + Sync + 'static {
+    type View;
+    type Command;
+
+    fn steer(&mut self, view: &Self::View, input: &ControllerInput) -> Self::Command;
+}
+
+// The input devices controllers can read from
+#[derive(SystemParam)]
+pub struct ControllerInput<'w, 's> {
+    pub keys: Res<'w, ButtonInput<KeyCode>>,
+    pub mouse_buttons: Res<'w, ButtonInput<MouseButton>>,
+    pub gamepads: Res<'w, Gamepads>,
+    pub gamepad_axes: Res<'w, Axis<GamepadAxis>>,
+    pub gamepad_buttons: Res<'w, ButtonInput<GamepadButton>>,
+    windows: Query<'w, 's, &'static Window, With<PrimaryWindow>>,
+    cameras: Query<'w, 's, (&'static Camera, &'static GlobalTransform)>,
+}
+
+impl ControllerInput<'_, '_> {
+    // Where the mouse is on the playfield, `None` if it's outside the window or there isn't one
+    pub fn cursor(&self) -> Option<Vec2> {
+        let (camera, transform) = self.cameras.get_single().ok()?;
+        cursor_position(self.windows.get_single().ok()?, camera, transform)
+    }
+}
+
+// The controller playing a paddle
+#[derive(Component)]
+pub struct Controller<V: 'static, C: 'static>(pub Box<dyn PaddleController<View = V, Command = C>>);
+
+impl<V: 'static, C: 'static> Controller<V, C> {
+    pub fn new(controller: impl PaddleController<View = V, Command = C>) -> Self {
+        Self(Box::new(controller))
+    }
+}
+
+// Steered from outside the game, by a training environment or a bot on the network.
+// Clones share the same command, so keep one to `set` and give the other to the paddle
+pub struct External<V, C>(Arc<Mutex<C>>, PhantomData<fn(&V)>);
+
+impl<V, C> External<V, C> {
+    pub fn set(&self, command: C) {
+        *self.0.lock().unwrap() = command;
+    }
+}
+
+impl<V, C> Clone for External<V, C> {
+    fn clone(&self) -> Self {
+        Self(self.0.clone(), PhantomData)
+    }
+}
+
+impl<V, C: Default> Default for External<V, C> {
+    fn default() -> Self {
+        Self(Arc::default(), PhantomData)
+    }
+}
+
+impl<V: 'static, C: Clone + Send + 'static> PaddleController for External<V, C> {
+    type View = V;
+    type Command = C;
+
+    fn steer(&mut self, _view: &V, _input: &ControllerInput) -> C {
+        self.0.lock().unwrap().clone()
+    }
+}
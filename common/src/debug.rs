@@ -0,0 +1,135 @@
+use std::marker::PhantomData;
+
+use bevy::{
+    diagnostic::{DiagnosticsStore, FrameTimeDiagnosticsPlugin},
+    ecs::entity::Entities,
+    prelude::*,
+};
+
+use crate::components::{LastContact, Velocity};
+
+pub const COLLIDER_COLOR: Color = Color::srgb(1.0, 0.2, 0.2);
+pub const PADDLE_COLOR: Color = Color::srgb(0.2, 1.0, 0.2);
+const BALL_COLOR: Color = Color::srgb(0.2, 0.6, 1.0);
+const CONTACT_COLOR: Color = Color::srgb(1.0, 1.0, 0.2);
+// Length the contact normal is drawn at
+const NORMAL_LENGTH: f32 = 30.;
+
+// F3 shows what's going on under the hood: the bounding circle of each ball (anything with
+// a `B`), where the ball last touched something and which way it was pushed off, and the
+// frame rate, entity count and ball speed. Games draw their own colliders while the overlay
+// is showing, and add their own lines to the stats in `DebugInfo`.
+pub struct DebugOverlayPlugin<B> {
+    pub ball_radius: f32,
+    // Where on the screen the stats go
+    pub text_position: Style,
+    pub balls: PhantomData<fn() -> B>,
+}
+
+impl<B: Component> Plugin for DebugOverlayPlugin<B> {
+    fn build(&self, app: &mut App) {
+        if !app.is_plugin_added::<FrameTimeDiagnosticsPlugin>() {
+            app.add_plugins(FrameTimeDiagnosticsPlugin);
+        }
+        let text_position = self.text_position.clone();
+
+        app.init_resource::<DebugOverlay>()
+            .init_resource::<DebugLines>()
+            .init_resource::<LastContact>()
+            .insert_resource(BallRadius(self.ball_radius))
+            .add_systems(Startup, move |commands: Commands| spawn_debug_text(commands, text_position.clone()))
+            .add_systems(Update, toggle_debug_overlay)
+            .add_systems(
+                Update,
+                (draw_balls::<B>, show_debug_stats::<B>.after(DebugInfo)).run_if(resource_equals(DebugOverlay(true))),
+            );
+    }
+}
+
+// Whether the overlay is showing
+#[derive(Resource, Default, PartialEq)]
+pub struct DebugOverlay(pub bool);
+
+// The game's own lines for the stats, filled in each frame by systems in `DebugInfo`
+#[derive(Resource, Default)]
+pub struct DebugLines(pub Vec<String>);
+
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct DebugInfo;
+
+#[derive(Resource)]
+struct BallRadius(f32);
+
+#[derive(Component)]
+struct DebugText;
+
+fn spawn_debug_text(mut commands: Commands, position: Style) {
+    commands.spawn((
+        TextBundle::from_section(
+            "",
+            TextStyle {
+                font_size: 18.0,
+                color: CONTACT_COLOR,
+                ..default()
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            ..position
+        }),
+        Visibility::Hidden,
+        DebugText,
+    ));
+}
+
+fn toggle_debug_overlay(
+    keys: Res<ButtonInput<KeyCode>>,
+    mut overlay: ResMut<DebugOverlay>,
+    mut text: Query<&mut Visibility, With<DebugText>>,
+) {
+    if !keys.just_pressed(KeyCode::F3) {
+        return;
+    }
+    overlay.0 = !overlay.0;
+    for mut visibility in text.iter_mut() {
+        *visibility = if overlay.0 { Visibility::Inherited } else { Visibility::Hidden };
+    }
+}
+
+fn draw_balls<B: Component>(
+    mut gizmos: Gizmos,
+    balls: Query<&Transform, With<B>>,
+    radius: Res<BallRadius>,
+    last_contact: Res<LastContact>,
+) {
+    for transform in balls.iter() {
+        gizmos.circle_2d(transform.translation.truncate(), radius.0, BALL_COLOR);
+    }
+    if let Some(contact) = last_contact.0 {
+        gizmos.circle_2d(contact.point, 3., CONTACT_COLOR);
+        gizmos.arrow_2d(contact.point, contact.point + contact.normal * NORMAL_LENGTH, CONTACT_COLOR);
+    }
+}
+
+fn show_debug_stats<B: Component>(
+    diagnostics: Res<DiagnosticsStore>,
+    entities: &Entities,
+    balls: Query<&Velocity, With<B>>,
+    mut lines: ResMut<DebugLines>,
+    mut text: Query<&mut Text, With<DebugText>>,
+) {
+    let fps = diagnostics
+        .get(&FrameTimeDiagnosticsPlugin::FPS)
+        .and_then(|fps| fps.smoothed())
+        .unwrap_or(0.);
+    let speeds: Vec<String> = balls.iter().map(|velocity| format!("{:.0}", velocity.0.length())).collect();
+    let mut stats = format!("FPS: {:.0}\nEntities: {}\nBall speed: {}", fps, entities.len(), speeds.join(", "));
+    for line in lines.0.drain(..) {
+        stats.push('\n');
+        stats.push_str(&line);
+    }
+
+    for mut text in text.iter_mut() {
+        text.sections[0].value = stats.clone();
+    }
+}
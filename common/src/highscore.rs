@@ -0,0 +1,548 @@
+use std::cmp::Reverse;
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::ErrorKind;
+use std::marker::PhantomData;
+use std::path::{Path, PathBuf};
+
+use bevy::{
+    input::{
+        keyboard::{Key, KeyboardInput},
+        ButtonState,
+    },
+    prelude::*,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::settings::data_dir;
+use crate::title::{AnyInput, GameScreen};
+
+// Entries kept in each table
+pub const TABLE_SIZE: usize = 10;
+// Letters in a name
+pub const NAME_LENGTH: usize = 3;
+// Seconds the leaderboard stays up before going back to the title screen
+const LEADERBOARD_TIME: f32 = 15.;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HighScore {
+    pub name: String,
+    pub score: u32,
+}
+
+// The best scores in each mode, kept in a JSON file between games
+#[derive(Resource, Debug, Default)]
+pub struct HighScores {
+    // Where the tables are saved, `None` to keep them only until the game exits
+    path: Option<PathBuf>,
+    tables: BTreeMap<String, Vec<HighScore>>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct HighScoreFile<T> {
+    #[serde(default)]
+    version: u32,
+    tables: BTreeMap<String, Vec<T>>,
+}
+
+impl HighScores {
+    pub fn default_path(game: &str) -> Option<PathBuf> {
+        Some(data_dir(game)?.join("highscores.json"))
+    }
+
+    // Never fails: a missing file starts empty tables, and a damaged one keeps whatever
+    // entries can still be read
+    pub fn load(path: Option<PathBuf>) -> Self {
+        let tables = path.as_deref().map(read_tables).unwrap_or_default();
+        Self { path, tables }
+    }
+
+    pub fn save(&self) -> Result<(), String> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        let failed = |error: std::io::Error| format!("Couldn't save high scores to {}: {}", path.display(), error);
+
+        if let Some(directory) = path.parent() {
+            fs::create_dir_all(directory).map_err(failed)?;
+        }
+        let file = HighScoreFile {
+            version: 1,
+            tables: self.tables.clone(),
+        };
+        let text = serde_json::to_string_pretty(&file).expect("high scores are always serializable");
+        // Written alongside and then moved into place, so a crash halfway through a save
+        // can't damage the tables
+        let partial = path.with_extension("json.partial");
+        fs::write(&partial, text).and_then(|_| fs::rename(&partial, path)).map_err(failed)
+    }
+
+    pub fn table(&self, mode: &str) -> &[HighScore] {
+        self.tables.get(mode).map_or(&[], Vec::as_slice)
+    }
+
+    pub fn qualifies(&self, mode: &str, score: u32) -> bool {
+        let table = self.table(mode);
+        score > 0 && (table.len() < TABLE_SIZE || table.last().is_some_and(|last| score > last.score))
+    }
+
+    // Adds a score to the mode's table, returning its place from 0 if it made the table.
+    // Ties go below the scores that got there first
+    pub fn insert(&mut self, mode: &str, name: &str, score: u32) -> Option<usize> {
+        let table = self.tables.entry(mode.to_string()).or_default();
+        let place = table.iter().position(|entry| score > entry.score).unwrap_or(table.len());
+        if place >= TABLE_SIZE {
+            return None;
+        }
+        table.insert(
+            place,
+            HighScore {
+                name: clean_name(name),
+                score,
+            },
+        );
+        table.truncate(TABLE_SIZE);
+        Some(place)
+    }
+}
+
+// Reads whatever it can from a saved file. Entries that can't be read are dropped, and a
+// file that can't be read at all is moved aside instead of being overwritten by the next save
+fn read_tables(path: &Path) -> BTreeMap<String, Vec<HighScore>> {
+    let text = match fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(error) if error.kind() == ErrorKind::NotFound => return BTreeMap::new(),
+        Err(error) => {
+            warn!("Couldn't read high scores from {}: {}", path.display(), error);
+            return BTreeMap::new();
+        }
+    };
+
+    let file: HighScoreFile<Value> = match serde_json::from_str(&text) {
+        Ok(file) => file,
+        Err(error) => {
+            let damaged = path.with_extension("json.damaged");
+            warn!(
+                "High scores in {} are damaged ({}), starting afresh and keeping the old file as {}",
+                path.display(),
+                error,
+                damaged.display()
+            );
+            let _ = fs::rename(path, &damaged);
+            return BTreeMap::new();
+        }
+    };
+
+    let mut dropped = 0;
+    let tables = file
+        .tables
+        .into_iter()
+        .map(|(mode, entries)| {
+            let count = entries.len();
+            let mut table: Vec<HighScore> = entries
+                .into_iter()
+                .filter_map(|entry| serde_json::from_value::<HighScore>(entry).ok())
+                .map(|entry| HighScore {
+                    name: clean_name(&entry.name),
+                    score: entry.score,
+                })
+                .collect();
+            dropped += count - table.len();
+            table.sort_by_key(|entry| Reverse(entry.score));
+            table.truncate(TABLE_SIZE);
+            (mode, table)
+        })
+        .collect();
+    if dropped > 0 {
+        warn!("Skipped {} damaged high scores in {}", dropped, path.display());
+    }
+    tables
+}
+
+// Up to NAME_LENGTH capital letters and digits
+fn clean_name(name: &str) -> String {
+    let name: String = name
+        .chars()
+        .filter(char::is_ascii_alphanumeric)
+        .map(|letter| letter.to_ascii_uppercase())
+        .take(NAME_LENGTH)
+        .collect();
+    if name.is_empty() {
+        "???".to_string()
+    } else {
+        name
+    }
+}
+
+// Sent by a game when a game started from the title screen, or a demo, is finished
+#[derive(Event)]
+pub struct GameFinished {
+    // The player's final score
+    pub score: u32,
+}
+
+// Once a game started from the title screen is finished, offers a place in the mode's
+// high-score table if the score is good enough, and shows the table. It can also be shown
+// from the title screen.
+pub struct HighScorePlugin<S> {
+    // Where to save the tables, `None` to keep them only until the game exits
+    pub path: Option<PathBuf>,
+    // The table to use
+    pub mode: String,
+    // Whether the player's games are recorded at all
+    pub record: bool,
+    pub screens: PhantomData<fn() -> S>,
+}
+
+impl<S: GameScreen> Plugin for HighScorePlugin<S> {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(HighScores::load(self.path.clone()))
+            .insert_resource(HighScoreMode {
+                mode: self.mode.clone(),
+                record: self.record,
+            })
+            .init_resource::<NameEntry>()
+            .add_event::<GameFinished>()
+            .add_systems(
+                Update,
+                end_game::<S>
+                    .run_if(on_event::<GameFinished>())
+                    .run_if(in_state(S::DEMO).or_else(in_state(S::PLAYING))),
+            )
+            .add_systems(OnEnter(S::NAME_ENTRY), spawn_name_entry::<S>)
+            .add_systems(Update, (enter_name::<S>, show_name).chain().run_if(in_state(S::NAME_ENTRY)))
+            .add_systems(OnEnter(S::LEADERBOARD), spawn_leaderboard::<S>)
+            .add_systems(OnExit(S::LEADERBOARD), |mut commands: Commands| {
+                commands.remove_resource::<LastGame>();
+            })
+            .add_systems(Update, leaderboard::<S>.run_if(in_state(S::LEADERBOARD)));
+    }
+}
+
+#[derive(Resource)]
+struct HighScoreMode {
+    mode: String,
+    record: bool,
+}
+
+// How the last game went, shown on the leaderboard
+#[derive(Resource)]
+struct LastGame {
+    score: u32,
+    // Its place in the table, if it made it
+    place: Option<usize>,
+}
+
+// The name being typed in, kept between games so it only needs typing once
+#[derive(Resource)]
+struct NameEntry {
+    letters: [char; NAME_LENGTH],
+    cursor: usize,
+    // Keys pressed before the screen came up, like the Enter that ended the game, are still
+    // waiting to be read on its first frame and mustn't count
+    fresh: bool,
+}
+
+impl Default for NameEntry {
+    fn default() -> Self {
+        Self {
+            letters: ['A'; NAME_LENGTH],
+            cursor: 0,
+            fresh: false,
+        }
+    }
+}
+
+#[derive(Component)]
+struct NameText;
+
+#[derive(Resource)]
+struct LeaderboardTimer(Timer);
+
+fn end_game<S: GameScreen>(
+    mut commands: Commands,
+    mut finished: EventReader<GameFinished>,
+    high_scores: Res<HighScores>,
+    mode: Res<HighScoreMode>,
+    screen: Res<State<S>>,
+    mut next_screen: ResMut<NextState<S>>,
+) {
+    let Some(score) = finished.read().last().map(|finished| finished.score) else {
+        return;
+    };
+
+    if *screen.get() == S::DEMO {
+        next_screen.set(S::TITLE);
+        return;
+    }
+    commands.insert_resource(LastGame { score, place: None });
+    if mode.record && high_scores.qualifies(&mode.mode, score) {
+        next_screen.set(S::NAME_ENTRY);
+    } else {
+        next_screen.set(S::LEADERBOARD);
+    }
+}
+
+fn spawn_name_entry<S: GameScreen>(mut commands: Commands, game: Res<LastGame>, mut entry: ResMut<NameEntry>) {
+    entry.cursor = 0;
+    entry.fresh = true;
+
+    commands
+        .spawn((
+            screen_node(),
+            StateScoped(S::NAME_ENTRY),
+        ))
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section("NEW HIGH SCORE", text_style(80.0)));
+            parent.spawn(TextBundle::from_section(game.score.to_string(), text_style(50.0)));
+            parent.spawn((TextBundle::default(), NameText));
+            parent.spawn(TextBundle::from_section(
+                "Type your initials, or change letters with the arrow keys. Enter to finish",
+                text_style(24.0),
+            ));
+        });
+}
+
+fn enter_name<S: GameScreen>(
+    mut keyboard: EventReader<KeyboardInput>,
+    gamepad_buttons: Res<ButtonInput<GamepadButton>>,
+    mut entry: ResMut<NameEntry>,
+    mut game: ResMut<LastGame>,
+    mode: Res<HighScoreMode>,
+    mut high_scores: ResMut<HighScores>,
+    mut next_screen: ResMut<NextState<S>>,
+) {
+    if entry.fresh {
+        keyboard.clear();
+        entry.fresh = false;
+        return;
+    }
+    let gamepad = |button_type| gamepad_buttons.get_just_pressed().any(|button| button.button_type == button_type);
+    let mut keys: Vec<Key> = keyboard
+        .read()
+        .filter(|event| event.state == ButtonState::Pressed)
+        .map(|event| event.logical_key.clone())
+        .collect();
+    for (button_type, key) in [
+        (GamepadButtonType::DPadUp, Key::ArrowUp),
+        (GamepadButtonType::DPadDown, Key::ArrowDown),
+        (GamepadButtonType::DPadLeft, Key::ArrowLeft),
+        (GamepadButtonType::DPadRight, Key::ArrowRight),
+        (GamepadButtonType::South, Key::Enter),
+    ] {
+        if gamepad(button_type) {
+            keys.push(key);
+        }
+    }
+
+    for key in keys {
+        let cursor = entry.cursor;
+        match key {
+            Key::Character(text) => {
+                if let Some(letter) = text.chars().next().filter(char::is_ascii_alphanumeric) {
+                    entry.letters[cursor] = letter.to_ascii_uppercase();
+                    entry.cursor = (cursor + 1).min(NAME_LENGTH - 1);
+                }
+            }
+            Key::ArrowUp => entry.letters[cursor] = cycle_letter(entry.letters[cursor], 1),
+            Key::ArrowDown => entry.letters[cursor] = cycle_letter(entry.letters[cursor], -1),
+            Key::ArrowLeft | Key::Backspace => entry.cursor = cursor.saturating_sub(1),
+            Key::ArrowRight => entry.cursor = (cursor + 1).min(NAME_LENGTH - 1),
+            Key::Enter => {
+                let name: String = entry.letters.iter().collect();
+                game.place = high_scores.insert(&mode.mode, &name, game.score);
+                if let Err(error) = high_scores.save() {
+                    warn!("{}", error);
+                }
+                next_screen.set(S::LEADERBOARD);
+                return;
+            }
+            _ => {}
+        }
+    }
+}
+
+// The next or previous letter, going A-Z then 0-9
+fn cycle_letter(letter: char, step: i32) -> char {
+    const LETTERS: &str = "ABCDEFGHIJKLMNOPQRSTUVWXYZ0123456789";
+    let index = LETTERS.find(letter).unwrap_or(0) as i32;
+    let index = (index + step).rem_euclid(LETTERS.len() as i32);
+    LETTERS.as_bytes()[index as usize] as char
+}
+
+fn show_name(entry: Res<NameEntry>, mut query: Query<&mut Text, With<NameText>>) {
+    if !entry.is_changed() {
+        return;
+    }
+    for mut text in query.iter_mut() {
+        text.sections = entry
+            .letters
+            .iter()
+            .enumerate()
+            .map(|(index, letter)| {
+                let mut style = text_style(70.0);
+                if index == entry.cursor {
+                    style.color = Color::srgb(1.0, 0.8, 0.2);
+                }
+                TextSection::new(format!(" {} ", letter), style)
+            })
+            .collect();
+    }
+}
+
+fn spawn_leaderboard<S: GameScreen>(
+    mut commands: Commands,
+    high_scores: Res<HighScores>,
+    mode: Res<HighScoreMode>,
+    last_game: Option<Res<LastGame>>,
+) {
+    commands.insert_resource(LeaderboardTimer(Timer::from_seconds(LEADERBOARD_TIME, TimerMode::Once)));
+
+    let table = high_scores.table(&mode.mode);
+    let place = last_game.as_ref().and_then(|game| game.place);
+    commands
+        .spawn((
+            screen_node(),
+            StateScoped(S::LEADERBOARD),
+        ))
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section("HIGH SCORES", text_style(80.0)));
+            parent.spawn(TextBundle::from_section(mode.mode.clone(), text_style(30.0)));
+            if table.is_empty() {
+                parent.spawn(TextBundle::from_section("No scores yet", text_style(30.0)));
+            }
+            for (index, entry) in table.iter().enumerate() {
+                let mut style = text_style(30.0);
+                if Some(index) == place {
+                    style.color = Color::srgb(1.0, 0.8, 0.2);
+                }
+                parent.spawn(TextBundle::from_section(
+                    format!("{:>2}.  {:<3}  {:>7}", index + 1, entry.name, entry.score),
+                    style,
+                ));
+            }
+            if let Some(game) = last_game.filter(|game| game.place.is_none()) {
+                parent.spawn(TextBundle::from_section(format!("Your score: {}", game.score), text_style(30.0)));
+            }
+        });
+}
+
+fn leaderboard<S: GameScreen>(
+    input: AnyInput,
+    time: Res<Time>,
+    mut timer: ResMut<LeaderboardTimer>,
+    mut next_screen: ResMut<NextState<S>>,
+) {
+    if input.pressed() || timer.0.tick(time.delta()).just_finished() {
+        next_screen.set(S::TITLE);
+    }
+}
+
+fn screen_node() -> NodeBundle {
+    NodeBundle {
+        style: Style {
+            width: Val::Percent(100.0),
+            height: Val::Percent(100.0),
+            position_type: PositionType::Absolute,
+            flex_direction: FlexDirection::Column,
+            align_items: AlignItems::Center,
+            justify_content: JustifyContent::Center,
+            row_gap: Val::Px(10.0),
+            ..default()
+        },
+        background_color: BackgroundColor(Color::BLACK.with_alpha(0.8)),
+        ..default()
+    }
+}
+
+fn text_style(font_size: f32) -> TextStyle {
+    TextStyle {
+        font_size,
+        ..default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temporary_file(name: &str, contents: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("highscores-{}-{}.json", name, std::process::id()));
+        fs::write(&path, contents).unwrap();
+        path
+    }
+
+    #[test]
+    fn insert_keeps_table_sorted_and_short() {
+        let mut scores = HighScores::default();
+        for score in 1..=TABLE_SIZE as u32 {
+            assert_eq!(scores.insert("mode", "abc", score * 100), Some(0));
+        }
+
+        assert!(!scores.qualifies("mode", 100));
+        assert!(scores.qualifies("mode", 150));
+        assert!(scores.qualifies("other", 1));
+        assert_eq!(scores.insert("mode", "new", 150), Some(TABLE_SIZE - 1));
+        assert_eq!(scores.insert("mode", "low", 50), None);
+
+        let table = scores.table("mode");
+        assert_eq!(table.len(), TABLE_SIZE);
+        assert_eq!(table[0].score, TABLE_SIZE as u32 * 100);
+        assert_eq!(table[TABLE_SIZE - 1], HighScore { name: "NEW".to_string(), score: 150 });
+    }
+
+    #[test]
+    fn ties_go_below_earlier_scores() {
+        let mut scores = HighScores::default();
+        scores.insert("mode", "AAA", 300);
+        assert_eq!(scores.insert("mode", "BBB", 300), Some(1));
+    }
+
+    #[test]
+    fn zero_never_qualifies() {
+        assert!(!HighScores::default().qualifies("mode", 0));
+    }
+
+    #[test]
+    fn load_skips_damaged_entries() {
+        let path = temporary_file(
+            "entries",
+            r#"{"version": 1, "tables": {"mode": [
+                {"name": "ok", "score": 200},
+                {"name": "BAD", "score": -5},
+                {"score": 10},
+                {"name": "TOP", "score": 900}
+            ]}}"#,
+        );
+        let scores = HighScores::load(Some(path.clone()));
+        fs::remove_file(path).unwrap();
+
+        let names: Vec<&str> = scores.table("mode").iter().map(|entry| entry.name.as_str()).collect();
+        assert_eq!(names, ["TOP", "OK"]);
+    }
+
+    #[test]
+    fn load_moves_unreadable_file_aside() {
+        let path = temporary_file("unreadable", "{\"tables\": {\"mode\": [");
+        let scores = HighScores::load(Some(path.clone()));
+        let damaged = path.with_extension("json.damaged");
+
+        assert!(scores.table("mode").is_empty());
+        assert!(!path.exists());
+        assert!(damaged.exists());
+        fs::remove_file(damaged).unwrap();
+    }
+
+    #[test]
+    fn save_and_load_round_trip() {
+        let path = std::env::temp_dir().join(format!("highscores-round-trip-{}/highscores.json", std::process::id()));
+        let mut scores = HighScores::load(Some(path.clone()));
+        scores.insert("mode", "xyz", 1234);
+        scores.save().unwrap();
+
+        let loaded = HighScores::load(Some(path.clone()));
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+        assert_eq!(loaded.table("mode"), scores.table("mode"));
+    }
+}
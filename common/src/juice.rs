@@ -0,0 +1,259 @@
+use std::marker::PhantomData;
+use std::time::Duration;
+
+use bevy::{prelude::*, time::TimeSystem, transform::TransformSystem};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+use crate::components::Velocity;
+use crate::settings::Settings;
+
+// Furthest the camera is thrown at full shake
+const MAX_SHAKE_OFFSET: f32 = 12.;
+// Shake lost per second
+const SHAKE_DECAY: f32 = 1.5;
+// How long a squashed paddle takes to spring back
+const SQUASH_TIME: f32 = 0.15;
+
+// Which effects are on. They're purely visual, so turning them off never changes how the
+// game plays
+#[derive(Resource, Debug, Clone, Copy, PartialEq)]
+pub struct JuiceSettings {
+    // Camera shake, from 0 for none to 1 for full strength
+    pub shake: f32,
+    pub hit_stop: bool,
+    pub squash_stretch: bool,
+}
+
+impl JuiceSettings {
+    pub const OFF: Self = Self {
+        shake: 0.,
+        hit_stop: false,
+        squash_stretch: false,
+    };
+}
+
+impl Default for JuiceSettings {
+    fn default() -> Self {
+        Self {
+            shake: 1.,
+            hit_stop: true,
+            squash_stretch: true,
+        }
+    }
+}
+
+// How a game's squash and stretch looks
+#[derive(Resource, Debug, Clone, Copy)]
+pub struct JuiceStyle {
+    // How much longer the ball looks along its velocity at `speed`
+    pub stretch: f32,
+    pub speed: f32,
+    // How far a paddle squashes when the ball hits it, along `squash_axis` (the one the ball
+    // comes in along), bulging out half as much the other way
+    pub squash: f32,
+    pub squash_axis: Vec2,
+}
+
+// Game feel that every game has: the camera shakes with the trauma the game adds to `Shake`,
+// paddles squash when the game squashes their `Juiced`, and balls stretch as they fly. What
+// shakes the camera and which entities are paddles are up to each game, as is starting a
+// hit-stop, which this ends again.
+//
+// Shake and squash are put on the transforms after the game has updated and taken off again
+// before it next runs, so the game itself never sees them. Reducing motion in the `Settings`
+// turns them all off.
+pub struct JuicePlugin<B> {
+    pub settings: JuiceSettings,
+    pub style: JuiceStyle,
+    pub balls: PhantomData<fn() -> B>,
+}
+
+impl<B: Component> Plugin for JuicePlugin<B> {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(self.settings)
+            .insert_resource(FullJuice(self.settings))
+            .insert_resource(self.style)
+            .init_resource::<Shake>()
+            .init_resource::<HitStop>()
+            .add_systems(First, (undo_effects, end_hit_stop.after(TimeSystem)))
+            .add_systems(Update, add_juice::<B>)
+            .add_systems(Update, follow_settings.run_if(resource_exists_and_changed::<Settings>))
+            .add_systems(
+                PostUpdate,
+                (shake_camera, squash_and_stretch::<B>).before(TransformSystem::TransformPropagate),
+            );
+    }
+}
+
+// The effects as the plugin was given them, for when motion isn't reduced
+#[derive(Resource)]
+struct FullJuice(JuiceSettings);
+
+// Trauma from recent impacts, which the camera shakes with the square of
+#[derive(Resource)]
+pub struct Shake {
+    trauma: f32,
+    // Separate from the game's own randomness, so effects never change how a seeded game plays
+    rng: StdRng,
+}
+
+impl Default for Shake {
+    fn default() -> Self {
+        Self {
+            trauma: 0.,
+            rng: StdRng::from_entropy(),
+        }
+    }
+}
+
+impl Shake {
+    pub fn add(&mut self, trauma: f32) {
+        self.trauma = (self.trauma + trauma).min(1.);
+    }
+}
+
+// Real time left on the current hit-stop
+#[derive(Resource, Default)]
+pub struct HitStop(Option<Timer>);
+
+impl HitStop {
+    // Freezes the game for `length` of real time, unless something else already has
+    pub fn start(&mut self, length: Duration, time: &mut Time<Virtual>) {
+        if time.is_paused() && self.0.is_none() {
+            return;
+        }
+        time.pause();
+        self.0 = Some(Timer::new(length, TimerMode::Once));
+    }
+}
+
+// Something the effects move about. Balls and cameras get one by themselves, and each game
+// gives its paddles one
+#[derive(Component, Default)]
+pub struct Juiced {
+    // The transform the game gave it, while an effect is showing
+    original: Option<Transform>,
+    // Seconds of squash left
+    squash: f32,
+}
+
+impl Juiced {
+    pub fn squash(&mut self) {
+        self.squash = SQUASH_TIME;
+    }
+}
+
+// Reducing motion turns every effect off
+fn follow_settings(settings: Res<Settings>, full: Res<FullJuice>, mut juice: ResMut<JuiceSettings>) {
+    *juice = if settings.reduces_motion() { JuiceSettings::OFF } else { full.0 };
+}
+
+#[allow(clippy::type_complexity)]
+fn add_juice<B: Component>(mut commands: Commands, added: Query<Entity, Or<(Added<B>, Added<Camera2d>)>>) {
+    for entity in added.iter() {
+        commands.entity(entity).insert(Juiced::default());
+    }
+}
+
+fn undo_effects(mut query: Query<(&mut Transform, &mut Juiced)>) {
+    for (mut transform, mut juiced) in query.iter_mut() {
+        if let Some(original) = juiced.original.take() {
+            *transform = original;
+        }
+    }
+}
+
+fn end_hit_stop(real_time: Res<Time<Real>>, mut hit_stop: ResMut<HitStop>, mut time: ResMut<Time<Virtual>>) {
+    let Some(timer) = &mut hit_stop.0 else {
+        return;
+    };
+    if timer.tick(real_time.delta()).finished() {
+        hit_stop.0 = None;
+        time.unpause();
+    }
+}
+
+// Runs on real time, so the camera keeps shaking through a hit-stop
+fn shake_camera(
+    time: Res<Time<Real>>,
+    settings: Res<JuiceSettings>,
+    mut shake: ResMut<Shake>,
+    mut cameras: Query<(&mut Transform, &mut Juiced), With<Camera2d>>,
+) {
+    if shake.trauma <= 0. {
+        return;
+    }
+    shake.trauma = (shake.trauma - SHAKE_DECAY * time.delta_seconds()).max(0.);
+    if settings.shake <= 0. {
+        return;
+    }
+
+    let strength = MAX_SHAKE_OFFSET * settings.shake * shake.trauma * shake.trauma;
+    let offset = Vec2::new(shake.rng.gen_range(-1.0..=1.0), shake.rng.gen_range(-1.0..=1.0)) * strength;
+    for (mut transform, mut juiced) in cameras.iter_mut() {
+        juiced.original = Some(*transform);
+        transform.translation += offset.extend(0.);
+    }
+}
+
+#[allow(clippy::type_complexity)]
+fn squash_and_stretch<B: Component>(
+    time: Res<Time<Real>>,
+    settings: Res<JuiceSettings>,
+    style: Res<JuiceStyle>,
+    mut query: Query<(&mut Transform, &mut Juiced, Option<&Velocity>, Has<B>), Without<Camera2d>>,
+) {
+    for (mut transform, mut juiced, velocity, is_ball) in query.iter_mut() {
+        juiced.squash = (juiced.squash - time.delta_seconds()).max(0.);
+        if !settings.squash_stretch {
+            continue;
+        }
+
+        if is_ball {
+            let Some(velocity) = velocity.map(|velocity| velocity.0.truncate()) else {
+                continue;
+            };
+            if velocity == Vec2::ZERO {
+                continue;
+            }
+            let stretch = 1. + style.stretch * velocity.length() / style.speed;
+            juiced.original = Some(*transform);
+            transform.rotation = Quat::from_rotation_z(velocity.to_angle());
+            transform.scale.x *= stretch;
+            transform.scale.y /= stretch;
+        } else if juiced.squash > 0. {
+            // Flattened by the ball coming in, and springing back
+            let squash = style.squash * juiced.squash / SQUASH_TIME;
+            let bulge = Vec2::ONE - style.squash_axis;
+            juiced.original = Some(*transform);
+            transform.scale *= (Vec2::ONE - style.squash_axis * squash + bulge * squash / 2.).extend(1.);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn trauma_tops_out_at_one() {
+        let mut shake = Shake::default();
+        shake.add(0.6);
+        shake.add(0.6);
+        assert_eq!(shake.trauma, 1.);
+    }
+
+    #[test]
+    fn a_hit_stop_leaves_time_paused_by_something_else_alone() {
+        let mut time = Time::<Virtual>::default();
+        time.pause();
+        let mut hit_stop = HitStop::default();
+        hit_stop.start(Duration::from_millis(50), &mut time);
+        assert!(hit_stop.0.is_none());
+
+        time.unpause();
+        hit_stop.start(Duration::from_millis(50), &mut time);
+        assert!(time.is_paused());
+        assert!(hit_stop.0.is_some());
+    }
+}
@@ -0,0 +1,14 @@
+// The parts the games have in common, which each of them builds on with its own rules
+
+pub mod components;
+pub mod controller;
+pub mod debug;
+pub mod highscore;
+pub mod juice;
+pub mod particles;
+pub mod playfield;
+pub mod remote;
+pub mod settings;
+pub mod timescale;
+pub mod title;
+pub mod trail;
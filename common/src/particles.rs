@@ -0,0 +1,265 @@
+use std::f32::consts::PI;
+
+use bevy::{
+    prelude::*,
+    render::{
+        mesh::{Indices, PrimitiveTopology, VertexAttributeValues},
+        render_asset::RenderAssetUsages,
+    },
+    sprite::{MaterialMesh2dBundle, Mesh2dHandle},
+};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+// Most particles alive at once. Bursts are cut short while the pool is full
+pub const MAX_PARTICLES: usize = 4096;
+// Fraction of its speed a particle keeps after a second
+const PARTICLE_DRAG: f32 = 0.2;
+// In front of everything else on the field
+const PARTICLE_Z: f32 = 10.;
+
+// Particles simulated on the CPU and drawn as a single mesh. The particles live in a pool
+// that's allocated once, so even thousands of them don't allocate anything from frame to
+// frame. Games throw them out with `Particles::burst` before `ParticleSystems` runs.
+pub struct ParticlesPlugin;
+
+impl Plugin for ParticlesPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(Particles::default())
+            .add_systems(Startup, spawn_particle_mesh)
+            .add_systems(
+                Update,
+                (simulate_particles, draw_particles).chain().in_set(ParticleSystems),
+            );
+    }
+}
+
+// Moving the particles on and drawing them
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ParticleSystems;
+
+#[derive(Clone, Copy)]
+struct Particle {
+    position: Vec2,
+    velocity: Vec2,
+    age: f32,
+    lifetime: f32,
+    size: f32,
+    gravity: f32,
+    color: LinearRgba,
+}
+
+// A handful of particles thrown out at once
+#[derive(Clone, Copy)]
+pub struct Burst {
+    pub position: Vec2,
+    // Particles start anywhere in a box this size around `position`
+    pub area: Vec2,
+    // Where the particles head, and how far off that they can stray in radians (PI for any way)
+    pub direction: Vec2,
+    pub spread: f32,
+    pub count: usize,
+    // The fastest a particle starts out, the slowest go at a third of it
+    pub speed: f32,
+    // Seconds the longest lived particles last
+    pub lifetime: f32,
+    pub size: f32,
+    pub gravity: f32,
+    pub color: Color,
+}
+
+impl Default for Burst {
+    fn default() -> Self {
+        Self {
+            position: Vec2::ZERO,
+            area: Vec2::ZERO,
+            direction: Vec2::Y,
+            spread: PI,
+            count: 10,
+            speed: 100.,
+            lifetime: 0.5,
+            size: 4.,
+            gravity: 0.,
+            color: Color::WHITE,
+        }
+    }
+}
+
+#[derive(Resource)]
+pub struct Particles {
+    pool: Vec<Particle>,
+    // Separate from the game's own randomness, so effects never change how a seeded game plays
+    rng: StdRng,
+}
+
+impl Default for Particles {
+    fn default() -> Self {
+        Self {
+            pool: Vec::with_capacity(MAX_PARTICLES),
+            rng: StdRng::from_entropy(),
+        }
+    }
+}
+
+impl Particles {
+    pub fn burst(&mut self, burst: Burst) {
+        let count = burst.count.min(MAX_PARTICLES - self.pool.len());
+        let color = LinearRgba::from(burst.color);
+        for _ in 0..count {
+            let angle = self.rng.gen_range(-burst.spread..=burst.spread);
+            let speed = burst.speed * self.rng.gen_range(0.33..=1.);
+            let offset = Vec2::new(self.rng.gen_range(-0.5..=0.5), self.rng.gen_range(-0.5..=0.5)) * burst.area;
+            self.pool.push(Particle {
+                position: burst.position + offset,
+                velocity: Vec2::from_angle(angle).rotate(burst.direction.normalize_or_zero()) * speed,
+                age: 0.,
+                lifetime: burst.lifetime * self.rng.gen_range(0.5..=1.),
+                size: burst.size,
+                gravity: burst.gravity,
+                color,
+            });
+        }
+    }
+
+    // Moves every particle on by `delta` seconds, dropping the ones that have had their time
+    pub fn step(&mut self, delta: f32) {
+        let drag = PARTICLE_DRAG.powf(delta);
+        self.pool.retain_mut(|particle| {
+            particle.age += delta;
+            particle.velocity.y += particle.gravity * delta;
+            particle.velocity *= drag;
+            particle.position += particle.velocity * delta;
+            particle.age < particle.lifetime
+        });
+    }
+
+    pub fn count(&self) -> usize {
+        self.pool.len()
+    }
+}
+
+// Where all the particles are drawn
+#[derive(Component)]
+struct ParticleMesh;
+
+fn spawn_particle_mesh(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+) {
+    // A quad per particle, with room for a full pool so filling it never reallocates
+    let mesh = Mesh::new(PrimitiveTopology::TriangleList, RenderAssetUsages::default())
+        .with_inserted_attribute(Mesh::ATTRIBUTE_POSITION, Vec::<[f32; 3]>::with_capacity(MAX_PARTICLES * 4))
+        .with_inserted_attribute(Mesh::ATTRIBUTE_COLOR, Vec::<[f32; 4]>::with_capacity(MAX_PARTICLES * 4))
+        .with_inserted_indices(Indices::U32(Vec::with_capacity(MAX_PARTICLES * 6)));
+
+    commands.spawn((
+        MaterialMesh2dBundle {
+            mesh: Mesh2dHandle(meshes.add(mesh)),
+            material: materials.add(Color::WHITE),
+            transform: Transform::from_xyz(0., 0., PARTICLE_Z),
+            visibility: Visibility::Hidden,
+            ..default()
+        },
+        ParticleMesh,
+    ));
+}
+
+fn simulate_particles(time: Res<Time>, mut particles: ResMut<Particles>) {
+    if !particles.pool.is_empty() {
+        particles.step(time.delta_seconds());
+    }
+}
+
+// Rewrites the mesh from the pool, with each particle shrinking and fading out as it ages
+fn draw_particles(
+    particles: Res<Particles>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut query: Query<(&Mesh2dHandle, &mut Visibility), With<ParticleMesh>>,
+) {
+    let Ok((handle, mut visibility)) = query.get_single_mut() else {
+        return;
+    };
+    if particles.pool.is_empty() {
+        // Nothing left to redraw once it's hidden
+        if *visibility != Visibility::Hidden {
+            *visibility = Visibility::Hidden;
+        }
+        return;
+    }
+    *visibility = Visibility::Inherited;
+    let Some(mesh) = meshes.get_mut(&handle.0) else {
+        return;
+    };
+
+    if let Some(VertexAttributeValues::Float32x3(positions)) = mesh.attribute_mut(Mesh::ATTRIBUTE_POSITION) {
+        positions.clear();
+        for particle in &particles.pool {
+            let half = particle.size * (1. - 0.5 * particle.age / particle.lifetime) / 2.;
+            let Vec2 { x, y } = particle.position;
+            positions.extend([[x - half, y - half, 0.], [x + half, y - half, 0.], [x + half, y + half, 0.], [x - half, y + half, 0.]]);
+        }
+    }
+    if let Some(VertexAttributeValues::Float32x4(colors)) = mesh.attribute_mut(Mesh::ATTRIBUTE_COLOR) {
+        colors.clear();
+        for particle in &particles.pool {
+            let fade = 1. - particle.age / particle.lifetime;
+            let color = particle.color.with_alpha(particle.color.alpha * fade).to_f32_array();
+            colors.extend([color; 4]);
+        }
+    }
+    if let Some(Indices::U32(indices)) = mesh.indices_mut() {
+        indices.clear();
+        for quad in 0..particles.pool.len() as u32 {
+            let first = quad * 4;
+            indices.extend([first, first + 1, first + 2, first, first + 2, first + 3]);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f32::consts::FRAC_PI_4;
+
+    use super::*;
+
+    #[test]
+    fn bursts_stop_at_the_pool_size() {
+        let mut particles = Particles::default();
+        for _ in 0..3 {
+            particles.burst(Burst {
+                count: MAX_PARTICLES / 2,
+                ..default()
+            });
+        }
+        assert_eq!(particles.count(), MAX_PARTICLES);
+        assert_eq!(particles.pool.capacity(), MAX_PARTICLES);
+    }
+
+    #[test]
+    fn particles_expire_after_their_lifetime() {
+        let mut particles = Particles::default();
+        particles.burst(Burst {
+            count: 50,
+            lifetime: 1.,
+            ..default()
+        });
+        particles.step(0.4);
+        assert!(particles.count() > 0);
+        particles.step(0.61);
+        assert_eq!(particles.count(), 0);
+    }
+
+    #[test]
+    fn particles_head_within_the_spread() {
+        let mut particles = Particles::default();
+        particles.burst(Burst {
+            direction: Vec2::X,
+            spread: FRAC_PI_4,
+            count: 100,
+            ..default()
+        });
+        for particle in &particles.pool {
+            assert!(particle.velocity.angle_between(Vec2::X).abs() <= FRAC_PI_4 + 1e-4);
+        }
+    }
+}
@@ -0,0 +1,120 @@
+use bevy::{
+    prelude::*,
+    render::camera::{ScalingMode, Viewport},
+    window::{CursorGrabMode, PrimaryWindow, WindowMode},
+};
+
+// The logical play area in world units. Gameplay only ever works in these units,
+// the camera takes care of fitting them to whatever size the window is
+#[derive(Resource, Debug, Clone, Copy)]
+pub struct Playfield {
+    pub width: f32,
+    pub height: f32,
+}
+
+impl Default for Playfield {
+    fn default() -> Self {
+        Self {
+            width: 1280.,
+            height: 720.,
+        }
+    }
+}
+
+impl Playfield {
+    pub fn size(&self) -> Vec2 {
+        Vec2::new(self.width, self.height)
+    }
+
+    pub fn left(&self) -> f32 {
+        -self.width / 2.
+    }
+
+    pub fn right(&self) -> f32 {
+        self.width / 2.
+    }
+
+    pub fn top(&self) -> f32 {
+        self.height / 2.
+    }
+
+    pub fn bottom(&self) -> f32 {
+        -self.height / 2.
+    }
+}
+
+// Where the mouse is on the playfield, if it's over the window
+pub fn cursor_position(window: &Window, camera: &Camera, camera_transform: &GlobalTransform) -> Option<Vec2> {
+    let position = window.cursor_position()?;
+    // The cursor position is relative to the window, but the camera is letterboxed inside it
+    let viewport = camera.logical_viewport_rect()?;
+    camera.viewport_to_world_2d(camera_transform, position - viewport.min)
+}
+
+// Letterboxes the camera so the whole playfield is visible at its own aspect ratio
+pub fn fit_camera_to_window(
+    playfield: Res<Playfield>,
+    windows: Query<Ref<Window>, With<PrimaryWindow>>,
+    mut cameras: Query<(&mut Camera, &mut OrthographicProjection)>,
+) {
+    let Ok(window) = windows.get_single() else {
+        return;
+    };
+    if !window.is_changed() && !playfield.is_changed() {
+        return;
+    }
+
+    let window_size = window.physical_size().as_vec2();
+    if window_size.min_element() < 1. {
+        // Minimized
+        return;
+    }
+
+    let scale = (window_size / playfield.size()).min_element();
+    let viewport_size = (playfield.size() * scale).floor().max(Vec2::ONE);
+    let viewport_position = ((window_size - viewport_size) / 2.).floor();
+
+    for (mut camera, mut projection) in cameras.iter_mut() {
+        camera.viewport = Some(Viewport {
+            physical_position: viewport_position.as_uvec2(),
+            physical_size: viewport_size.as_uvec2(),
+            ..default()
+        });
+        projection.scaling_mode = ScalingMode::Fixed {
+            width: playfield.width,
+            height: playfield.height,
+        };
+    }
+}
+
+pub fn toggle_fullscreen(
+    key: Res<ButtonInput<KeyCode>>,
+    mut windows: Query<&mut Window, With<PrimaryWindow>>,
+) {
+    if !key.just_pressed(KeyCode::F11) {
+        return;
+    }
+
+    for mut window in windows.iter_mut() {
+        window.mode = match window.mode {
+            WindowMode::Windowed => WindowMode::BorderlessFullscreen,
+            _ => WindowMode::Windowed,
+        };
+    }
+}
+
+// Hides the mouse cursor and keeps it inside the window, so it can't wander off while it's
+// steering the paddle
+pub fn grab_cursor(mut windows: Query<&mut Window, With<PrimaryWindow>>) {
+    for mut window in windows.iter_mut() {
+        window.cursor.grab_mode = CursorGrabMode::Confined;
+        window.cursor.visible = false;
+    }
+}
+
+pub fn release_cursor(mut windows: Query<&mut Window, With<PrimaryWindow>>) {
+    for mut window in windows.iter_mut() {
+        window.cursor.grab_mode = CursorGrabMode::None;
+        window.cursor.visible = true;
+    }
+}
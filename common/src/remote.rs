@@ -0,0 +1,289 @@
+use std::io::{self, ErrorKind, Read, Write};
+use std::net::{Ipv4Addr, SocketAddr, TcpListener, TcpStream};
+
+use bevy::prelude::*;
+use serde::{de::DeserializeOwned, Serialize};
+
+use crate::controller::{Controller, External};
+
+// How much a client can have waiting either way, a line it hasn't finished sending or state
+// it hasn't read yet, before it's dropped
+pub const REMOTE_BUFFER_LIMIT: usize = 64 * 1024;
+
+// A localhost TCP port that bots connect to, talking one line of JSON at a time each way.
+// Nothing ever blocks the game: clients that send a line longer than REMOTE_BUFFER_LIMIT,
+// or fall that far behind reading, get dropped
+pub struct RemoteServer {
+    listener: TcpListener,
+    clients: Vec<RemoteClient>,
+}
+
+struct RemoteClient {
+    stream: TcpStream,
+    // Bytes received that don't make up a full line yet
+    received: Vec<u8>,
+    // Bytes the socket wouldn't take yet, sent before anything newer
+    unsent: Vec<u8>,
+}
+
+impl RemoteServer {
+    pub fn bind(port: u16) -> io::Result<Self> {
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, port))?;
+        listener.set_nonblocking(true)?;
+        Ok(Self {
+            listener,
+            clients: Vec::new(),
+        })
+    }
+
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.listener.local_addr()
+    }
+
+    pub fn has_clients(&self) -> bool {
+        !self.clients.is_empty()
+    }
+
+    // Lets in everyone who's connected since the last call
+    pub fn accept(&mut self) {
+        loop {
+            match self.listener.accept() {
+                Ok((stream, address)) => {
+                    if stream.set_nonblocking(true).is_err() {
+                        continue;
+                    }
+                    let _ = stream.set_nodelay(true);
+                    info!("Remote client connected from {}", address);
+                    self.clients.push(RemoteClient {
+                        stream,
+                        received: Vec::new(),
+                        unsent: Vec::new(),
+                    });
+                }
+                Err(error) if error.kind() == ErrorKind::WouldBlock => break,
+                Err(error) => {
+                    warn!("Remote control accept failed: {}", error);
+                    break;
+                }
+            }
+        }
+    }
+
+    // Every line that's come in since the last call, from all the clients. Lines that aren't
+    // a `T` are skipped
+    pub fn receive<T: DeserializeOwned>(&mut self) -> Vec<T> {
+        let mut messages = Vec::new();
+        self.clients.retain_mut(|client| {
+            let mut buffer = [0; 1024];
+            loop {
+                match client.stream.read(&mut buffer) {
+                    // Disconnected
+                    Ok(0) => return false,
+                    Ok(read) => client.received.extend_from_slice(&buffer[..read]),
+                    Err(error) if error.kind() == ErrorKind::WouldBlock => return true,
+                    Err(_) => return false,
+                }
+
+                while let Some(end) = client.received.iter().position(|&byte| byte == b'\n') {
+                    let line: Vec<u8> = client.received.drain(..=end).collect();
+                    match serde_json::from_slice(&line) {
+                        Ok(message) => messages.push(message),
+                        Err(error) => warn!("Ignoring bad remote command: {}", error),
+                    }
+                }
+                if client.received.len() > REMOTE_BUFFER_LIMIT {
+                    warn!("Dropping remote client, its line is over {} bytes", REMOTE_BUFFER_LIMIT);
+                    return false;
+                }
+            }
+        });
+        messages
+    }
+
+    // Sends `message` to every client as a line. Whatever a socket won't take straight away
+    // waits for the next send
+    pub fn send(&mut self, message: &impl Serialize) {
+        if self.clients.is_empty() {
+            return;
+        }
+        let mut line = serde_json::to_vec(message).expect("remote messages are always serializable");
+        line.push(b'\n');
+
+        self.clients.retain_mut(|client| {
+            client.unsent.extend_from_slice(&line);
+            match client.flush() {
+                Ok(()) if client.unsent.len() <= REMOTE_BUFFER_LIMIT => true,
+                Ok(()) => {
+                    warn!("Dropping remote client, it's over {} bytes behind", REMOTE_BUFFER_LIMIT);
+                    false
+                }
+                Err(_) => false,
+            }
+        });
+    }
+}
+
+impl RemoteClient {
+    // Writes as much as the socket will take without blocking
+    fn flush(&mut self) -> io::Result<()> {
+        while !self.unsent.is_empty() {
+            match self.stream.write(&self.unsent) {
+                Ok(0) => return Err(ErrorKind::WriteZero.into()),
+                Ok(written) => {
+                    self.unsent.drain(..written);
+                }
+                Err(error) if error.kind() == ErrorKind::WouldBlock => break,
+                Err(error) if error.kind() == ErrorKind::Interrupted => continue,
+                Err(error) => return Err(error),
+            }
+        }
+        Ok(())
+    }
+}
+
+// A paddle bots can take over by swapping in an `External` controller
+pub struct RemotePaddle<V: 'static, C: 'static> {
+    steering: External<V, C>,
+    // The paddle entity that's been taken over, a new one gets taken over afresh
+    taken_over: Option<Entity>,
+    // What the paddle was playing with before, to hand back when the bots leave
+    benched: Option<Controller<V, C>>,
+}
+
+impl<V: 'static, C: Default + 'static> Default for RemotePaddle<V, C> {
+    fn default() -> Self {
+        Self {
+            steering: External::default(),
+            taken_over: None,
+            benched: None,
+        }
+    }
+}
+
+impl<V: 'static, C: Clone + Send + 'static> RemotePaddle<V, C> {
+    pub fn is_taken_over(&self) -> bool {
+        self.taken_over.is_some()
+    }
+
+    // `None` hands the paddle back
+    pub fn steer(
+        &mut self,
+        command: Option<C>,
+        paddle: Entity,
+        controller: Option<Mut<Controller<V, C>>>,
+        commands: &mut Commands,
+    ) {
+        match (command, self.taken_over == Some(paddle)) {
+            (Some(command), false) => {
+                self.taken_over = Some(paddle);
+                self.steering.set(command);
+                let remote = Controller::new(self.steering.clone());
+                match controller {
+                    Some(mut controller) => self.benched = Some(std::mem::replace(&mut *controller, remote)),
+                    None => {
+                        commands.entity(paddle).insert(remote);
+                    }
+                }
+            }
+            (Some(command), true) => self.steering.set(command),
+            (None, true) => {
+                self.taken_over = None;
+                match (self.benched.take(), controller) {
+                    (Some(benched), Some(mut controller)) => *controller = benched,
+                    (Some(benched), None) => {
+                        commands.entity(paddle).insert(benched);
+                    }
+                    (None, _) => {
+                        commands.entity(paddle).remove::<Controller<V, C>>();
+                    }
+                }
+            }
+            // The paddle that was taken over is gone, along with its controller
+            (None, false) => {
+                self.taken_over = None;
+                self.benched = None;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::thread;
+    use std::time::Duration;
+
+    use serde_json::{json, Value};
+
+    use super::*;
+
+    fn connect(server: &mut RemoteServer) -> TcpStream {
+        let client = TcpStream::connect(server.local_addr().unwrap()).unwrap();
+        for _ in 0..100 {
+            server.accept();
+            if server.has_clients() {
+                return client;
+            }
+            thread::sleep(Duration::from_millis(10));
+        }
+        panic!("the client never got accepted");
+    }
+
+    // Keeps receiving until `done` says so, as the bytes can take a moment to arrive
+    fn receive_until(server: &mut RemoteServer, mut done: impl FnMut(&RemoteServer, &[Value]) -> bool) -> Vec<Value> {
+        let mut messages = Vec::new();
+        for _ in 0..100 {
+            messages.extend(server.receive::<Value>());
+            if done(server, &messages) {
+                return messages;
+            }
+            thread::sleep(Duration::from_millis(10));
+        }
+        panic!("gave up receiving, got {:?}", messages);
+    }
+
+    #[test]
+    fn lines_come_in_whole_and_bad_ones_are_skipped() {
+        let mut server = RemoteServer::bind(0).unwrap();
+        let mut client = connect(&mut server);
+        client.write_all(b"{\"paddle\": \"left\"}\nnot json\n{\"launch\"").unwrap();
+        client.write_all(b": true}\n").unwrap();
+
+        let messages = receive_until(&mut server, |_, messages| messages.len() == 2);
+        assert_eq!(messages, [json!({"paddle": "left"}), json!({"launch": true})]);
+        assert!(server.has_clients());
+    }
+
+    #[test]
+    fn clients_are_dropped_for_overlong_lines() {
+        let mut server = RemoteServer::bind(0).unwrap();
+        let mut client = connect(&mut server);
+        client.write_all(&vec![b' '; REMOTE_BUFFER_LIMIT + 1]).unwrap();
+
+        receive_until(&mut server, |server, _| !server.has_clients());
+    }
+
+    #[test]
+    fn clients_are_dropped_once_they_fall_too_far_behind() {
+        let mut server = RemoteServer::bind(0).unwrap();
+        // Never reads, so everything past what the sockets can hold waits to be sent
+        let _client = connect(&mut server);
+        server.send(&"x".repeat(16 * 1024 * 1024));
+
+        assert!(!server.has_clients());
+    }
+
+    #[test]
+    fn clients_keep_up_if_they_read() {
+        let mut server = RemoteServer::bind(0).unwrap();
+        let mut client = connect(&mut server);
+        let message = "x".repeat(REMOTE_BUFFER_LIMIT / 2);
+        let mut read = 0;
+        let mut buffer = vec![0; 1024 * 1024];
+        for _ in 0..100 {
+            server.send(&message);
+            read += client.read(&mut buffer).unwrap();
+        }
+        assert!(server.has_clients());
+        assert!(read > 0);
+    }
+}
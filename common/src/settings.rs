@@ -0,0 +1,157 @@
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+// The player's settings, kept between games in a JSON file next to the high scores. For now
+// that's whether to reduce motion, which M on the title screen turns on and off.
+pub struct SettingsPlugin {
+    // Where to save the settings, `None` to keep them only until the game exits
+    pub path: Option<PathBuf>,
+    // Reduces motion for this run whatever the saved setting, without saving it
+    pub reduce_motion: bool,
+}
+
+impl Plugin for SettingsPlugin {
+    fn build(&self, app: &mut App) {
+        let mut settings = Settings::load(self.path.clone());
+        settings.forced_reduce_motion = self.reduce_motion;
+        app.insert_resource(settings);
+    }
+}
+
+#[derive(Resource, Debug, Default)]
+pub struct Settings {
+    path: Option<PathBuf>,
+    // Screen shake, hit-stop and squash and stretch are all turned off. This is the saved
+    // setting, see `reduces_motion` for whether motion is actually reduced
+    pub reduce_motion: bool,
+    // Reduced for this run by `SettingsPlugin`, and never saved
+    forced_reduce_motion: bool,
+}
+
+#[derive(Serialize, Deserialize, Default)]
+struct SettingsFile {
+    #[serde(default)]
+    version: u32,
+    #[serde(default)]
+    reduce_motion: bool,
+}
+
+// The platform's usual place for the game's data, `None` if there's no home directory to put it in
+pub fn data_dir(game: &str) -> Option<PathBuf> {
+    let var = |name: &str| std::env::var_os(name).filter(|value| !value.is_empty()).map(PathBuf::from);
+    let data = if cfg!(windows) {
+        var("APPDATA")?
+    } else if cfg!(target_os = "macos") {
+        var("HOME")?.join("Library/Application Support")
+    } else {
+        var("XDG_DATA_HOME").or_else(|| Some(var("HOME")?.join(".local/share")))?
+    };
+    Some(data.join(game))
+}
+
+impl Settings {
+    pub fn default_path(game: &str) -> Option<PathBuf> {
+        Some(data_dir(game)?.join("settings.json"))
+    }
+
+    // Never fails: a missing or damaged file leaves everything at the defaults
+    pub fn load(path: Option<PathBuf>) -> Self {
+        let file = path.as_deref().map(read_settings).unwrap_or_default();
+        Self {
+            path,
+            reduce_motion: file.reduce_motion,
+            forced_reduce_motion: false,
+        }
+    }
+
+    pub fn reduces_motion(&self) -> bool {
+        self.reduce_motion || self.forced_reduce_motion
+    }
+
+    // Whether motion is reduced for this run whatever the saved setting
+    pub fn reduce_motion_forced(&self) -> bool {
+        self.forced_reduce_motion
+    }
+
+    pub fn save(&self) -> Result<(), String> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        let failed = |error: std::io::Error| format!("Couldn't save settings to {}: {}", path.display(), error);
+
+        if let Some(directory) = path.parent() {
+            fs::create_dir_all(directory).map_err(failed)?;
+        }
+        let file = SettingsFile {
+            version: 1,
+            reduce_motion: self.reduce_motion,
+        };
+        let text = serde_json::to_string_pretty(&file).expect("settings are always serializable");
+        // Written alongside and then moved into place, like the high scores
+        let partial = path.with_extension("json.partial");
+        fs::write(&partial, text).and_then(|_| fs::rename(&partial, path)).map_err(failed)
+    }
+}
+
+fn read_settings(path: &Path) -> SettingsFile {
+    let text = match fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(error) if error.kind() == ErrorKind::NotFound => return SettingsFile::default(),
+        Err(error) => {
+            warn!("Couldn't read settings from {}: {}", path.display(), error);
+            return SettingsFile::default();
+        }
+    };
+    serde_json::from_str(&text).unwrap_or_else(|error| {
+        warn!("Settings in {} are damaged ({}), using the defaults", path.display(), error);
+        SettingsFile::default()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temporary_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("settings-{}-{}/settings.json", name, std::process::id()))
+    }
+
+    #[test]
+    fn save_and_load_round_trip() {
+        let path = temporary_path("round-trip");
+        let mut settings = Settings::load(Some(path.clone()));
+        assert!(!settings.reduce_motion);
+        settings.reduce_motion = true;
+        settings.save().unwrap();
+        let loaded = Settings::load(Some(path.clone()));
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+        assert!(loaded.reduce_motion);
+    }
+
+    #[test]
+    fn forced_reduced_motion_isnt_saved() {
+        let path = temporary_path("forced");
+        let mut settings = Settings::load(Some(path.clone()));
+        settings.forced_reduce_motion = true;
+        assert!(settings.reduces_motion());
+        settings.save().unwrap();
+        let loaded = Settings::load(Some(path.clone()));
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+        assert!(!loaded.reduce_motion);
+        assert!(!loaded.reduces_motion());
+    }
+
+    #[test]
+    fn damaged_file_loads_the_defaults() {
+        let path = temporary_path("damaged");
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, "{\"reduce_motion\": tru").unwrap();
+        let settings = Settings::load(Some(path.clone()));
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+        assert!(!settings.reduce_motion);
+    }
+}
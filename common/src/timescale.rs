@@ -0,0 +1,246 @@
+use bevy::{prelude::*, time::TimeSystem};
+
+// The speeds F5 and F6 go through
+pub const TIME_SCALES: [f32; 6] = [0.1, 0.25, 0.5, 1., 2., 4.];
+
+// Slowing the game down, speeding it up, freezing it and advancing it a tick at a time, for
+// looking closely at collisions. F5 and F6 slow it down and speed it up, F7 freezes and
+// unfreezes it and F8 runs exactly one FixedUpdate tick of a frozen game. It all goes through
+// the virtual clock the fixed clock runs off, so nothing else needs to know about it
+pub struct TimeScalePlugin;
+
+impl Plugin for TimeScalePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<TimeScale>()
+            .add_systems(Startup, spawn_time_scale_text)
+            .add_systems(First, (apply_time_scale.before(TimeSystem), advance_time.after(TimeSystem)))
+            .add_systems(
+                Update,
+                (time_scale_keys, show_time_scale.run_if(resource_changed::<TimeScale>)).chain(),
+            );
+    }
+}
+
+#[derive(Resource, Debug, Clone, Copy, PartialEq)]
+pub struct TimeScale {
+    // How fast the game runs, 1 being normal speed
+    pub scale: f32,
+    pub frozen: bool,
+    // Ticks asked for and not run yet, one a frame
+    ticks: u32,
+}
+
+impl Default for TimeScale {
+    fn default() -> Self {
+        Self {
+            scale: 1.,
+            frozen: false,
+            ticks: 0,
+        }
+    }
+}
+
+impl TimeScale {
+    // Sets the speed, kept between the slowest and fastest of TIME_SCALES. Anything that
+    // isn't a number is turned down and leaves the speed as it was
+    pub fn set_scale(&mut self, scale: f32) -> bool {
+        if !scale.is_finite() {
+            return false;
+        }
+        self.scale = scale.clamp(TIME_SCALES[0], TIME_SCALES[TIME_SCALES.len() - 1]);
+        true
+    }
+
+    // Moves on to the next of TIME_SCALES up or down from the current speed
+    pub fn change_scale(&mut self, faster: bool) {
+        let next = if faster {
+            TIME_SCALES.iter().find(|&&scale| scale > self.scale)
+        } else {
+            TIME_SCALES.iter().rev().find(|&&scale| scale < self.scale)
+        };
+        if let Some(&scale) = next {
+            self.scale = scale;
+        }
+    }
+
+    // Runs another tick of a frozen game
+    pub fn advance_tick(&mut self) {
+        if self.frozen {
+            self.ticks += 1;
+        }
+    }
+}
+
+#[derive(Component)]
+struct TimeScaleText;
+
+fn spawn_time_scale_text(mut commands: Commands) {
+    commands.spawn((
+        TextBundle::from_section(
+            "",
+            TextStyle {
+                font_size: 24.0,
+                ..default()
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            bottom: Val::Px(10.0),
+            left: Val::Px(10.0),
+            ..default()
+        }),
+        TimeScaleText,
+    ));
+}
+
+fn time_scale_keys(keys: Res<ButtonInput<KeyCode>>, mut time_scale: ResMut<TimeScale>) {
+    if keys.just_pressed(KeyCode::F5) {
+        time_scale.change_scale(false);
+    }
+    if keys.just_pressed(KeyCode::F6) {
+        time_scale.change_scale(true);
+    }
+    if keys.just_pressed(KeyCode::F7) {
+        time_scale.frozen = !time_scale.frozen;
+    }
+    // Advancing a running game freezes it first
+    if keys.just_pressed(KeyCode::F8) {
+        if time_scale.frozen {
+            time_scale.advance_tick();
+        } else {
+            time_scale.frozen = true;
+        }
+    }
+}
+
+// Before the clocks are updated, so the frame that's starting already runs at the new speed.
+// A frozen game is kept paused even if something else (like a hit-stop) unpauses it
+fn apply_time_scale(time_scale: Res<TimeScale>, mut time: ResMut<Time<Virtual>>, mut was_frozen: Local<bool>) {
+    if time.relative_speed() != time_scale.scale {
+        time.set_relative_speed(time_scale.scale);
+    }
+    if time_scale.frozen {
+        time.pause();
+    } else if *was_frozen {
+        time.unpause();
+    }
+    *was_frozen = time_scale.frozen;
+}
+
+// Moves a frozen game's clock on by exactly a fixed timestep, whatever the real frame took. What's
+// left over from the last tick is always less than that, so it comes to exactly one tick
+fn advance_time(
+    mut time_scale: ResMut<TimeScale>,
+    fixed_time: Res<Time<Fixed>>,
+    mut virtual_time: ResMut<Time<Virtual>>,
+    mut time: ResMut<Time>,
+) {
+    if time_scale.ticks == 0 || !virtual_time.is_paused() {
+        return;
+    }
+    time_scale.ticks -= 1;
+    virtual_time.advance_by(fixed_time.timestep());
+    *time = virtual_time.as_generic();
+}
+
+fn show_time_scale(time_scale: Res<TimeScale>, mut query: Query<&mut Text, With<TimeScaleText>>) {
+    let value = match (time_scale.frozen, time_scale.scale) {
+        (true, scale) => format!("Frozen ({}x)   F8: next tick", scale),
+        (false, scale) if scale != 1. => format!("{}x", scale),
+        _ => String::new(),
+    };
+    for mut text in query.iter_mut() {
+        text.sections[0].value = value.clone();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+    use bevy::{input::InputPlugin, time::TimeUpdateStrategy};
+
+    #[test]
+    fn scale_changes_stop_at_the_slowest_and_fastest() {
+        let mut time_scale = TimeScale::default();
+        for _ in 0..10 {
+            time_scale.change_scale(false);
+        }
+        assert_eq!(time_scale.scale, 0.1);
+        for _ in 0..10 {
+            time_scale.change_scale(true);
+        }
+        assert_eq!(time_scale.scale, 4.);
+    }
+
+    #[test]
+    fn scales_in_between_move_to_the_next_one() {
+        let mut time_scale = TimeScale::default();
+        time_scale.set_scale(0.3);
+        time_scale.change_scale(true);
+        assert_eq!(time_scale.scale, 0.5);
+        assert!(time_scale.set_scale(100.));
+        assert_eq!(time_scale.scale, 4.);
+    }
+
+    #[test]
+    fn scales_that_arent_numbers_are_turned_down() {
+        let mut time_scale = TimeScale::default();
+        time_scale.set_scale(0.5);
+        assert!(!time_scale.set_scale(f32::NAN));
+        assert!(!time_scale.set_scale(f32::INFINITY));
+        assert!(!time_scale.set_scale(f32::NEG_INFINITY));
+        assert_eq!(time_scale.scale, 0.5);
+    }
+
+    #[test]
+    fn only_a_frozen_game_advances() {
+        let mut time_scale = TimeScale::default();
+        time_scale.advance_tick();
+        assert_eq!(time_scale.ticks, 0);
+        time_scale.frozen = true;
+        time_scale.advance_tick();
+        time_scale.advance_tick();
+        assert_eq!(time_scale.ticks, 2);
+    }
+
+    #[derive(Resource, Default)]
+    struct Ticks(u32);
+
+    // A frozen app running `frame` long frames, counting its fixed ticks
+    fn frozen_app(frame: Duration) -> App {
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, InputPlugin, TimeScalePlugin))
+            .insert_resource(TimeUpdateStrategy::ManualDuration(frame))
+            .insert_resource(Time::<Fixed>::from_hz(60.))
+            .init_resource::<Ticks>()
+            .add_systems(FixedUpdate, |mut ticks: ResMut<Ticks>| ticks.0 += 1);
+        // Part way into a tick, so there's some left over when it's frozen
+        for _ in 0..10 {
+            app.update();
+        }
+        app.world_mut().resource_mut::<TimeScale>().frozen = true;
+        app.update();
+        app
+    }
+
+    #[test]
+    fn a_frozen_game_runs_one_tick_per_advance_whatever_the_frame_rate() {
+        for frame in [Duration::from_millis(3), Duration::from_millis(7), Duration::from_millis(50)] {
+            let mut app = frozen_app(frame);
+            let before = app.world().resource::<Ticks>().0;
+            for _ in 0..10 {
+                app.update();
+            }
+            assert_eq!(app.world().resource::<Ticks>().0, before);
+
+            for advance in 1..=3 {
+                app.world_mut().resource_mut::<TimeScale>().advance_tick();
+                app.update();
+                app.update();
+                assert_eq!(app.world().resource::<Ticks>().0, before + advance);
+            }
+        }
+    }
+}
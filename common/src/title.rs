@@ -0,0 +1,301 @@
+use std::marker::PhantomData;
+
+use bevy::{ecs::system::SystemParam, prelude::*, state::state::FreelyMutableState};
+
+use crate::settings::Settings;
+
+// Seconds the title screen waits for input before the computer starts a demo game
+const ATTRACT_DELAY: f32 = 10.;
+// Seconds a demo game runs before going back to the title screen
+const DEMO_LENGTH: f32 = 60.;
+
+// The screens every game has, for the shared ones to move between
+pub trait GameScreen: FreelyMutableState + Copy {
+    const TITLE: Self;
+    // The computer playing while nobody's around
+    const DEMO: Self;
+    const PLAYING: Self;
+    // Typing a name in for a new high score
+    const NAME_ENTRY: Self;
+    const LEADERBOARD: Self;
+}
+
+// The title screen, and the attract mode that takes over when it's left alone: the
+// computer plays under a "Press any key" banner until someone touches the controls. Each
+// game sets up who plays the demo and its own games itself. With `Settings` around, M turns
+// reduced motion on and off.
+pub struct TitleScreenPlugin<S> {
+    pub name: &'static str,
+    pub font_size: f32,
+    screens: PhantomData<fn() -> S>,
+}
+
+impl<S> TitleScreenPlugin<S> {
+    pub fn new(name: &'static str, font_size: f32) -> Self {
+        Self {
+            name,
+            font_size,
+            screens: PhantomData,
+        }
+    }
+}
+
+impl<S: GameScreen> Plugin for TitleScreenPlugin<S> {
+    fn build(&self, app: &mut App) {
+        let (name, font_size) = (self.name, self.font_size);
+
+        app.add_systems(OnEnter(S::TITLE), move |commands: Commands, settings: Option<Res<Settings>>| {
+            spawn_title_screen::<S>(commands, settings, name, font_size)
+        })
+        .add_systems(OnEnter(S::DEMO), spawn_demo_banner::<S>)
+        .add_systems(
+            Update,
+            (title_screen::<S>, show_reduce_motion.run_if(resource_exists_and_changed::<Settings>))
+                .chain()
+                .run_if(in_state(S::TITLE)),
+        )
+        .add_systems(Update, demo::<S>.run_if(in_state(S::DEMO)));
+    }
+}
+
+// Counts down to the next automatic screen change
+#[derive(Resource)]
+struct ScreenTimer(Timer);
+
+#[derive(Component)]
+struct ReduceMotionText;
+
+// Keys that do something of their own anywhere in the games, like F11 for fullscreen, F3 for
+// the debug overlay and ` for the console, so they never count as any key
+const TOOL_KEYS: [KeyCode; 13] = [
+    KeyCode::F1,
+    KeyCode::F2,
+    KeyCode::F3,
+    KeyCode::F4,
+    KeyCode::F5,
+    KeyCode::F6,
+    KeyCode::F7,
+    KeyCode::F8,
+    KeyCode::F9,
+    KeyCode::F10,
+    KeyCode::F11,
+    KeyCode::F12,
+    KeyCode::Backquote,
+];
+
+// Any key, mouse button or gamepad button pressed this frame, apart from TOOL_KEYS
+#[derive(SystemParam)]
+pub struct AnyInput<'w> {
+    keys: Res<'w, ButtonInput<KeyCode>>,
+    mouse: Res<'w, ButtonInput<MouseButton>>,
+    gamepad_buttons: Res<'w, ButtonInput<GamepadButton>>,
+}
+
+impl AnyInput<'_> {
+    pub fn pressed(&self) -> bool {
+        self.keys.get_just_pressed().any(|key| !TOOL_KEYS.contains(key))
+            || self.mouse.get_just_pressed().next().is_some()
+            || self.gamepad_buttons.get_just_pressed().next().is_some()
+    }
+}
+
+fn spawn_title_screen<S: GameScreen>(
+    mut commands: Commands,
+    settings: Option<Res<Settings>>,
+    name: &str,
+    font_size: f32,
+) {
+    commands.insert_resource(ScreenTimer(Timer::from_seconds(ATTRACT_DELAY, TimerMode::Once)));
+
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    position_type: PositionType::Absolute,
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::Center,
+                    row_gap: Val::Px(20.0),
+                    ..default()
+                },
+                background_color: BackgroundColor(Color::BLACK.with_alpha(0.7)),
+                ..default()
+            },
+            StateScoped(S::TITLE),
+        ))
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section(
+                name,
+                TextStyle {
+                    font_size,
+                    ..default()
+                },
+            ));
+            parent.spawn(TextBundle::from_section(
+                "Press any key to play",
+                TextStyle {
+                    font_size: 30.0,
+                    ..default()
+                },
+            ));
+            parent.spawn(TextBundle::from_section(
+                "L for high scores",
+                TextStyle {
+                    font_size: 24.0,
+                    ..default()
+                },
+            ));
+            if let Some(settings) = settings {
+                parent.spawn((
+                    TextBundle::from_section(
+                        reduce_motion_label(&settings),
+                        TextStyle {
+                            font_size: 24.0,
+                            ..default()
+                        },
+                    ),
+                    ReduceMotionText,
+                ));
+            }
+        });
+}
+
+fn spawn_demo_banner<S: GameScreen>(mut commands: Commands) {
+    commands.insert_resource(ScreenTimer(Timer::from_seconds(DEMO_LENGTH, TimerMode::Once)));
+
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    width: Val::Percent(100.0),
+                    position_type: PositionType::Absolute,
+                    bottom: Val::Percent(10.0),
+                    justify_content: JustifyContent::Center,
+                    ..default()
+                },
+                ..default()
+            },
+            StateScoped(S::DEMO),
+        ))
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section(
+                "DEMO - Press any key",
+                TextStyle {
+                    font_size: 40.0,
+                    ..default()
+                },
+            ));
+        });
+}
+
+fn title_screen<S: GameScreen>(
+    input: AnyInput,
+    time: Res<Time>,
+    mut timer: ResMut<ScreenTimer>,
+    settings: Option<ResMut<Settings>>,
+    mut next_screen: ResMut<NextState<S>>,
+) {
+    if let Some(mut settings) = settings.filter(|_| input.keys.just_pressed(KeyCode::KeyM)) {
+        settings.reduce_motion = !settings.reduce_motion;
+        if let Err(error) = settings.save() {
+            warn!("{}", error);
+        }
+        timer.0.reset();
+    } else if input.keys.just_pressed(KeyCode::KeyL) {
+        next_screen.set(S::LEADERBOARD);
+    } else if input.pressed() {
+        next_screen.set(S::PLAYING);
+    } else if timer.0.tick(time.delta()).just_finished() {
+        next_screen.set(S::DEMO);
+    }
+}
+
+fn reduce_motion_label(settings: &Settings) -> String {
+    let saved = if settings.reduce_motion { "on" } else { "off" };
+    if settings.reduce_motion_forced() {
+        format!("M to reduce motion: {} (on for this run)", saved)
+    } else {
+        format!("M to reduce motion: {}", saved)
+    }
+}
+
+fn show_reduce_motion(settings: Res<Settings>, mut query: Query<&mut Text, With<ReduceMotionText>>) {
+    for mut text in query.iter_mut() {
+        text.sections[0].value = reduce_motion_label(&settings);
+    }
+}
+
+fn demo<S: GameScreen>(
+    input: AnyInput,
+    time: Res<Time>,
+    mut timer: ResMut<ScreenTimer>,
+    mut next_screen: ResMut<NextState<S>>,
+) {
+    if input.pressed() || timer.0.tick(time.delta()).just_finished() {
+        next_screen.set(S::TITLE);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::{
+        input::{
+            keyboard::{Key, KeyboardInput, NativeKey},
+            ButtonState, InputPlugin,
+        },
+        state::app::StatesPlugin,
+    };
+
+    use super::*;
+
+    #[derive(States, Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+    enum Screen {
+        #[default]
+        Title,
+        Demo,
+        Playing,
+        NameEntry,
+        Leaderboard,
+    }
+
+    impl GameScreen for Screen {
+        const TITLE: Self = Screen::Title;
+        const DEMO: Self = Screen::Demo;
+        const PLAYING: Self = Screen::Playing;
+        const NAME_ENTRY: Self = Screen::NameEntry;
+        const LEADERBOARD: Self = Screen::Leaderboard;
+    }
+
+    fn title_screen() -> App {
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, InputPlugin, StatesPlugin))
+            .init_state::<Screen>()
+            .add_plugins(TitleScreenPlugin::<Screen>::new("Test", 80.));
+        app.update();
+        app
+    }
+
+    fn press(app: &mut App, key_code: KeyCode) -> Screen {
+        for state in [ButtonState::Pressed, ButtonState::Released] {
+            app.world_mut().send_event(KeyboardInput {
+                key_code,
+                logical_key: Key::Unidentified(NativeKey::Unidentified),
+                state,
+                window: Entity::PLACEHOLDER,
+            });
+            app.update();
+        }
+        *app.world().resource::<State<Screen>>().get()
+    }
+
+    #[test]
+    fn tool_keys_dont_start_a_game() {
+        let mut app = title_screen();
+        for key in TOOL_KEYS {
+            assert_eq!(press(&mut app, key), Screen::Title, "{:?}", key);
+        }
+        assert_eq!(press(&mut app, KeyCode::Space), Screen::Playing);
+    }
+}
@@ -0,0 +1,279 @@
+use std::marker::PhantomData;
+
+use bevy::{
+    prelude::*,
+    render::{
+        mesh::{Indices, PrimitiveTopology, VertexAttributeValues},
+        render_asset::RenderAssetUsages,
+    },
+    sprite::{MaterialMesh2dBundle, Mesh2dHandle},
+};
+
+use crate::components::Velocity;
+
+// Most positions a trail remembers, one a frame
+pub const TRAIL_CAPACITY: usize = 32;
+// Positions drawn behind a ball going at the style's `speed`, faster balls draw more of them
+const TRAIL_POINTS: f32 = 12.;
+// A ball that moves further than this in a frame was put there, rather than flying there
+const TRAIL_BREAK: f32 = 100.;
+// Just behind the ball, which sits at 0, and still in front of the camera's near plane
+const TRAIL_Z: f32 = -0.05;
+
+// A fading trail behind each ball (anything with a `B`), longer and hotter the faster it
+// goes, so fast balls are easier to follow. All the trails are drawn as a single mesh.
+pub struct TrailPlugin<B> {
+    pub style: TrailStyle,
+    pub balls: PhantomData<fn() -> B>,
+}
+
+impl<B: Component> Plugin for TrailPlugin<B> {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(self.style)
+            .add_systems(Startup, spawn_trail_mesh)
+            .add_systems(
+                Update,
+                (add_trails::<B>, record_trails, draw_trails).chain().in_set(TrailSystems),
+            );
+    }
+}
+
+// Recording where the balls are and drawing their trails, once they've moved for the frame
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct TrailSystems;
+
+// How a game's trails look
+#[derive(Resource, Debug, Clone, Copy)]
+pub struct TrailStyle {
+    // How far the trail reaches to either side at the ball
+    pub radius: f32,
+    // The usual speed of a ball, which draws TRAIL_POINTS and a cold trail
+    pub speed: f32,
+    // The speed the trail is at its hottest
+    pub hot_speed: f32,
+}
+
+// Where a ball has been, as a ring buffer of its most recent positions
+#[derive(Component)]
+pub struct Trail {
+    points: [Vec2; TRAIL_CAPACITY],
+    // Where the next position goes
+    next: usize,
+    len: usize,
+}
+
+impl Default for Trail {
+    fn default() -> Self {
+        Self {
+            points: [Vec2::ZERO; TRAIL_CAPACITY],
+            next: 0,
+            len: 0,
+        }
+    }
+}
+
+impl Trail {
+    pub fn push(&mut self, point: Vec2) {
+        self.points[self.next] = point;
+        self.next = (self.next + 1) % TRAIL_CAPACITY;
+        self.len = (self.len + 1).min(TRAIL_CAPACITY);
+    }
+
+    pub fn clear(&mut self) {
+        self.len = 0;
+    }
+
+    pub fn latest(&self) -> Option<Vec2> {
+        self.iter().next()
+    }
+
+    // Newest first
+    pub fn iter(&self) -> impl Iterator<Item = Vec2> + '_ {
+        (1..=self.len).map(|age| self.points[(self.next + TRAIL_CAPACITY - age) % TRAIL_CAPACITY])
+    }
+}
+
+#[derive(Component)]
+struct TrailMesh;
+
+fn spawn_trail_mesh(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+) {
+    let mesh = Mesh::new(PrimitiveTopology::TriangleList, RenderAssetUsages::default())
+        .with_inserted_attribute(Mesh::ATTRIBUTE_POSITION, Vec::<[f32; 3]>::with_capacity(TRAIL_CAPACITY * 2))
+        .with_inserted_attribute(Mesh::ATTRIBUTE_COLOR, Vec::<[f32; 4]>::with_capacity(TRAIL_CAPACITY * 2))
+        .with_inserted_indices(Indices::U32(Vec::with_capacity(TRAIL_CAPACITY * 6)));
+
+    commands.spawn((
+        MaterialMesh2dBundle {
+            mesh: Mesh2dHandle(meshes.add(mesh)),
+            material: materials.add(Color::WHITE),
+            transform: Transform::from_xyz(0., 0., TRAIL_Z),
+            visibility: Visibility::Hidden,
+            ..default()
+        },
+        TrailMesh,
+    ));
+}
+
+fn add_trails<B: Component>(mut commands: Commands, balls: Query<Entity, Added<B>>) {
+    for ball in balls.iter() {
+        commands.entity(ball).insert(Trail::default());
+    }
+}
+
+fn record_trails(mut balls: Query<(&Transform, &Velocity, &mut Trail)>) {
+    for (transform, velocity, mut trail) in balls.iter_mut() {
+        let position = transform.translation.truncate();
+        match trail.latest() {
+            // Sitting on the paddle, or just put back on it
+            _ if velocity.0 == Vec3::ZERO => trail.clear(),
+            Some(latest) if latest.distance(position) > TRAIL_BREAK => {
+                trail.clear();
+                trail.push(position);
+            }
+            // Frozen for a moment
+            Some(latest) if latest == position => {}
+            _ => trail.push(position),
+        }
+    }
+}
+
+// How much of the trail to draw and how hot to color it, from 0 to 1, at a given speed
+fn trail_shape(speed: f32, style: &TrailStyle) -> (usize, f32) {
+    let points = (TRAIL_POINTS * speed / style.speed).round() as usize;
+    let heat = ((speed - style.speed) / (style.hot_speed - style.speed)).clamp(0., 1.);
+    (points.clamp(2, TRAIL_CAPACITY), heat)
+}
+
+// The trail as a ribbon, a pair of vertices for each point, narrowing and fading towards the tail
+fn trail_vertices(trail: &Trail, speed: f32, style: &TrailStyle) -> impl Iterator<Item = ([f32; 3], [f32; 4])> {
+    let (shown, heat) = trail_shape(speed, style);
+    let radius = style.radius;
+    let mut points = [Vec2::ZERO; TRAIL_CAPACITY];
+    let mut count = 0;
+    for point in trail.iter().take(shown) {
+        points[count] = point;
+        count += 1;
+    }
+
+    // A single point has no direction to draw a ribbon along
+    if count < 2 {
+        count = 0;
+    }
+
+    let color = LinearRgba::from(Color::srgb(0.6, 0.8, 1.0)).mix(&LinearRgba::from(Color::srgb(1.0, 0.45, 0.15)), heat);
+    (0..count).flat_map(move |index| {
+        let ahead = points[index.saturating_sub(1)];
+        let behind = points[(index + 1).min(count - 1)];
+        let side = (ahead - behind).normalize_or_zero().perp();
+        let fade = 1. - index as f32 / (count - 1) as f32;
+        let offset = side * radius * fade;
+        let color = color.with_alpha(0.6 * fade).to_f32_array();
+        let point = points[index];
+        [((point + offset).extend(0.).to_array(), color), ((point - offset).extend(0.).to_array(), color)]
+    })
+}
+
+fn draw_trails(
+    balls: Query<(&Trail, &Velocity)>,
+    style: Res<TrailStyle>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut query: Query<(&Mesh2dHandle, &mut Visibility), With<TrailMesh>>,
+) {
+    let Ok((handle, mut visibility)) = query.get_single_mut() else {
+        return;
+    };
+    let vertex_counts = || balls.iter().map(|(trail, velocity)| trail_vertices(trail, velocity.0.length(), &style).count());
+    if vertex_counts().sum::<usize>() == 0 {
+        if *visibility != Visibility::Hidden {
+            *visibility = Visibility::Hidden;
+        }
+        return;
+    }
+    *visibility = Visibility::Inherited;
+    let Some(mesh) = meshes.get_mut(&handle.0) else {
+        return;
+    };
+
+    if let Some(VertexAttributeValues::Float32x3(positions)) = mesh.attribute_mut(Mesh::ATTRIBUTE_POSITION) {
+        positions.clear();
+        for (trail, velocity) in balls.iter() {
+            positions.extend(trail_vertices(trail, velocity.0.length(), &style).map(|(position, _)| position));
+        }
+    }
+    if let Some(VertexAttributeValues::Float32x4(colors)) = mesh.attribute_mut(Mesh::ATTRIBUTE_COLOR) {
+        colors.clear();
+        for (trail, velocity) in balls.iter() {
+            colors.extend(trail_vertices(trail, velocity.0.length(), &style).map(|(_, color)| color));
+        }
+    }
+    if let Some(Indices::U32(indices)) = mesh.indices_mut() {
+        indices.clear();
+        let mut first = 0;
+        for count in vertex_counts() {
+            // A quad between each pair of points
+            for pair in (0..count.saturating_sub(2) as u32).step_by(2) {
+                let vertex = first + pair;
+                indices.extend([vertex, vertex + 1, vertex + 2, vertex + 1, vertex + 3, vertex + 2]);
+            }
+            first += count as u32;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const STYLE: TrailStyle = TrailStyle {
+        radius: 8.,
+        speed: 400.,
+        hot_speed: 900.,
+    };
+
+    #[test]
+    fn trail_lists_newest_first() {
+        let mut trail = Trail::default();
+        for x in 0..3 {
+            trail.push(Vec2::new(x as f32, 0.));
+        }
+        let xs: Vec<f32> = trail.iter().map(|point| point.x).collect();
+        assert_eq!(xs, [2., 1., 0.]);
+    }
+
+    #[test]
+    fn trail_keeps_only_the_latest_points() {
+        let mut trail = Trail::default();
+        for x in 0..TRAIL_CAPACITY + 5 {
+            trail.push(Vec2::new(x as f32, 0.));
+        }
+        assert_eq!(trail.iter().count(), TRAIL_CAPACITY);
+        assert_eq!(trail.latest(), Some(Vec2::new((TRAIL_CAPACITY + 4) as f32, 0.)));
+        assert_eq!(trail.iter().last(), Some(Vec2::new(5., 0.)));
+    }
+
+    #[test]
+    fn cleared_trail_draws_nothing() {
+        let mut trail = Trail::default();
+        trail.push(Vec2::ZERO);
+        trail.push(Vec2::X);
+        trail.clear();
+        assert_eq!(trail.latest(), None);
+        assert_eq!(trail_vertices(&trail, STYLE.speed, &STYLE).count(), 0);
+    }
+
+    #[test]
+    fn faster_balls_draw_longer_trails() {
+        let mut trail = Trail::default();
+        for x in 0..TRAIL_CAPACITY {
+            trail.push(Vec2::new(x as f32 * 10., 0.));
+        }
+        let slow = trail_vertices(&trail, STYLE.speed, &STYLE).count();
+        let fast = trail_vertices(&trail, STYLE.hot_speed, &STYLE).count();
+        assert_eq!(slow, TRAIL_POINTS as usize * 2);
+        assert!(fast > slow);
+    }
+}
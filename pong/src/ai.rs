@@ -0,0 +1,93 @@
+use std::str::FromStr;
+
+use bevy::prelude::*;
+
+use crate::controller::{ControllerInput, PaddleController, PaddleView};
+use crate::playfield::{Playfield, Scoreboard};
+use crate::BALL_RADIUS;
+
+// How close the predicting AI needs to be to where it wants to go before it stops moving
+const PREDICT_DEADZONE: f32 = 10.;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum AiKind {
+    // Chases the ball's height while it's coming, otherwise heads back to the middle
+    #[default]
+    Follow,
+    // Works out where the ball will reach the paddle, wall bounces included, and waits there
+    Predict,
+}
+
+impl AiKind {
+    pub const ALL: [AiKind; 2] = [AiKind::Follow, AiKind::Predict];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            AiKind::Follow => "follow",
+            AiKind::Predict => "predict",
+        }
+    }
+}
+
+impl PaddleController for AiKind {
+    type View = PaddleView;
+    type Command = f32;
+
+    fn steer(&mut self, view: &PaddleView, _input: &ControllerInput) -> f32 {
+        let moving_away = view.ball_velocity.x * view.facing > 0.;
+        match self {
+            AiKind::Follow => {
+                let target = if moving_away { 0. } else { view.ball_position.y };
+                if view.paddle.y < target {
+                    1.
+                } else if view.paddle.y > target {
+                    -1.
+                } else {
+                    0.
+                }
+            }
+            AiKind::Predict => {
+                let target = if moving_away {
+                    0.
+                } else {
+                    predict_y(view.ball_position, view.ball_velocity, view.paddle.x, &view.playfield)
+                };
+                let gap = target - view.paddle.y;
+                if gap.abs() < PREDICT_DEADZONE {
+                    0.
+                } else {
+                    gap.signum()
+                }
+            }
+        }
+    }
+}
+
+impl FromStr for AiKind {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        AiKind::ALL
+            .into_iter()
+            .find(|kind| kind.name() == name)
+            .ok_or_else(|| format!("unknown AI \"{}\"", name))
+    }
+}
+
+// Height the ball will be at when it reaches `x`, folding in bounces off the floor and ceiling
+fn predict_y(ball_position: Vec3, ball_velocity: Vec3, x: f32, playfield: &Playfield) -> f32 {
+    if ball_velocity.x == 0. {
+        return ball_position.y;
+    }
+
+    let time = (x - ball_position.x) / ball_velocity.x;
+    let bottom = playfield.bottom() + BALL_RADIUS;
+    let span = playfield.ceiling() - BALL_RADIUS - bottom;
+
+    let unfolded = (ball_position.y + ball_velocity.y * time - bottom).rem_euclid(span * 2.);
+    if unfolded > span {
+        bottom + span * 2. - unfolded
+    } else {
+        bottom + unfolded
+    }
+}
@@ -0,0 +1,56 @@
+use bevy::prelude::*;
+
+use crate::components::{Collider, Wall};
+use crate::playfield::{Playfield, Scoreboard};
+
+const WALL_THICKNESS: f32 = 40.;
+
+// A solid box the ball bounces off
+#[derive(Debug, Clone, Copy)]
+pub struct WallSpec {
+    pub position: Vec2,
+    pub size: Vec2,
+}
+
+// The walls and obstacles that make up the play area. Anything not covered by a
+// wall is open, and the ball leaving through the left or right side is a point
+#[derive(Resource, Debug, Clone, Default)]
+pub struct Arena {
+    pub walls: Vec<WallSpec>,
+}
+
+impl Arena {
+    // A wall along the bottom of the playfield and one under the scoreboard,
+    // with both ends left open as goals
+    pub fn classic(playfield: &Playfield) -> Self {
+        Arena::default()
+            .with_wall(
+                Vec2::new(0., playfield.bottom() - WALL_THICKNESS / 2.),
+                Vec2::new(playfield.width, WALL_THICKNESS),
+            )
+            .with_wall(
+                Vec2::new(0., playfield.ceiling() + WALL_THICKNESS / 2.),
+                Vec2::new(playfield.width, WALL_THICKNESS),
+            )
+    }
+
+    pub fn with_wall(mut self, position: Vec2, size: Vec2) -> Self {
+        self.walls.push(WallSpec { position, size });
+        self
+    }
+}
+
+// The walls are just colliders, the playfield edges and scoreboard line already show where they are
+pub fn spawn_arena(commands: &mut Commands, arena: &Arena) {
+    for wall in arena.walls.iter() {
+        commands.spawn((
+            TransformBundle::from_transform(Transform {
+                translation: wall.position.extend(0.0),
+                scale: wall.size.extend(1.0),
+                ..default()
+            }),
+            Wall,
+            Collider,
+        ));
+    }
+}
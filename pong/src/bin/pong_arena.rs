@@ -0,0 +1,215 @@
+// Plays AI controllers against each other headlessly and reports how they did:
+//
+//   cargo run --release --bin pong_arena -- --left follow --right predict --matches 50
+//
+// Options (all optional):
+//   --left <ai>       AI on the left paddle (follow, predict), defaults to follow
+//   --right <ai>      AI on the right paddle, defaults to follow
+//   --matches <n>     Number of matches to play, defaults to 20
+//   --points <n>      Points needed to win a match, defaults to 11
+//   --seed <n>        Seed of the first match, each match after it adds one, defaults to 0
+//   --json            Print the results as JSON instead of a table
+use std::num::{IntErrorKind, ParseIntError};
+use std::process::ExitCode;
+use std::str::FromStr;
+
+use bevy::prelude::*;
+use serde::Serialize;
+
+use pong::ai::AiKind;
+use pong::components::{Score, Scored, Scorer};
+use pong::controller::ControllerSetup;
+use pong::env::headless_app;
+use pong::PongPlugin;
+
+// A match nobody has won after 30 minutes of game time is called a draw
+const MAX_MATCH_FRAMES: u32 = 60 * 60 * 30;
+
+struct Options {
+    left: AiKind,
+    right: AiKind,
+    matches: u32,
+    points: u32,
+    seed: u64,
+    json: bool,
+}
+
+// Returns of the ball for every point played
+#[derive(Resource, Default)]
+struct Rallies(Vec<u32>);
+
+struct MatchResult {
+    left_points: u32,
+    right_points: u32,
+    rallies: Vec<u32>,
+}
+
+#[derive(Serialize)]
+struct SideReport {
+    ai: &'static str,
+    wins: u32,
+    win_rate: f32,
+    points_per_match: f32,
+}
+
+#[derive(Serialize)]
+struct Report {
+    matches: u32,
+    points_to_win: u32,
+    first_seed: u64,
+    left: SideReport,
+    right: SideReport,
+    draws: u32,
+    average_rally: f32,
+}
+
+fn main() -> ExitCode {
+    let options = match parse_options(std::env::args().skip(1)) {
+        Ok(options) => options,
+        Err(error) => {
+            eprintln!("{}", error);
+            return ExitCode::from(2);
+        }
+    };
+
+    let results: Vec<MatchResult> = (0..options.matches)
+        .map(|index| play_match(&options, options.seed + index as u64))
+        .collect();
+    let report = build_report(&options, &results);
+
+    if options.json {
+        println!("{}", serde_json::to_string_pretty(&report).unwrap());
+    } else {
+        print_table(&report);
+    }
+
+    ExitCode::SUCCESS
+}
+
+fn parse_options(mut args: impl Iterator<Item = String>) -> Result<Options, String> {
+    let mut options = Options {
+        left: AiKind::Follow,
+        right: AiKind::Follow,
+        matches: 20,
+        points: 11,
+        seed: 0,
+        json: false,
+    };
+
+    while let Some(arg) = args.next() {
+        if arg == "--json" {
+            options.json = true;
+            continue;
+        }
+
+        let value = args.next().ok_or_else(|| format!("{} needs a value", arg))?;
+        match arg.as_str() {
+            "--left" => options.left = value.parse()?,
+            "--right" => options.right = value.parse()?,
+            "--matches" => options.matches = number(&arg, &value)?,
+            "--points" => options.points = number::<u32>(&arg, &value)?.max(1),
+            "--seed" => options.seed = number(&arg, &value)?,
+            _ => return Err(format!("unknown option {}", arg)),
+        }
+    }
+
+    Ok(options)
+}
+
+// The option's value as a whole number, turned down if it's too big for it rather than cut short
+fn number<T: FromStr<Err = ParseIntError>>(arg: &str, value: &str) -> Result<T, String> {
+    value.parse().map_err(|error: ParseIntError| match error.kind() {
+        IntErrorKind::PosOverflow => format!("{} is too big, got \"{}\"", arg, value),
+        _ => format!("{} needs a number, got \"{}\"", arg, value),
+    })
+}
+
+fn play_match(options: &Options, seed: u64) -> MatchResult {
+    let mut app = headless_app(PongPlugin { seed: Some(seed) });
+    app.insert_resource(ControllerSetup::new(options.left, options.right))
+        .init_resource::<Rallies>()
+        .add_systems(Update, record_rallies);
+    app.finish();
+    app.cleanup();
+
+    for _ in 0..MAX_MATCH_FRAMES {
+        app.update();
+
+        let score = app.world().resource::<Score>();
+        if score.player >= options.points || score.opponent >= options.points {
+            break;
+        }
+    }
+
+    let score = app.world().resource::<Score>();
+    MatchResult {
+        left_points: score.player,
+        right_points: score.opponent,
+        rallies: app.world_mut().remove_resource::<Rallies>().unwrap_or_default().0,
+    }
+}
+
+fn record_rallies(mut events: EventReader<Scored>, mut rallies: ResMut<Rallies>) {
+    rallies.0.extend(events.read().map(|event| event.rally));
+}
+
+fn build_report(options: &Options, results: &[MatchResult]) -> Report {
+    let matches = results.len().max(1) as f32;
+    let winner = |result: &MatchResult| {
+        if result.left_points >= options.points {
+            Some(Scorer::Player)
+        } else if result.right_points >= options.points {
+            Some(Scorer::Opponent)
+        } else {
+            None
+        }
+    };
+    let left_wins = results.iter().filter(|result| matches!(winner(result), Some(Scorer::Player))).count() as u32;
+    let right_wins = results.iter().filter(|result| matches!(winner(result), Some(Scorer::Opponent))).count() as u32;
+    let rallies: Vec<u32> = results.iter().flat_map(|result| result.rallies.iter().copied()).collect();
+
+    Report {
+        matches: results.len() as u32,
+        points_to_win: options.points,
+        first_seed: options.seed,
+        left: SideReport {
+            ai: options.left.name(),
+            wins: left_wins,
+            win_rate: left_wins as f32 / matches,
+            points_per_match: results.iter().map(|result| result.left_points).sum::<u32>() as f32 / matches,
+        },
+        right: SideReport {
+            ai: options.right.name(),
+            wins: right_wins,
+            win_rate: right_wins as f32 / matches,
+            points_per_match: results.iter().map(|result| result.right_points).sum::<u32>() as f32 / matches,
+        },
+        draws: results.len() as u32 - left_wins - right_wins,
+        average_rally: rallies.iter().sum::<u32>() as f32 / rallies.len().max(1) as f32,
+    }
+}
+
+fn print_table(report: &Report) {
+    println!(
+        "{} matches to {} points (seeds {}..{})",
+        report.matches,
+        report.points_to_win,
+        report.first_seed,
+        report.first_seed + report.matches as u64
+    );
+    println!();
+    println!("{:<8} {:<10} {:>5} {:>9} {:>13}", "Side", "AI", "Wins", "Win rate", "Points/match");
+    for (side, stats) in [("Left", &report.left), ("Right", &report.right)] {
+        println!(
+            "{:<8} {:<10} {:>5} {:>8.1}% {:>13.2}",
+            side,
+            stats.ai,
+            stats.wins,
+            stats.win_rate * 100.,
+            stats.points_per_match
+        );
+    }
+    println!();
+    println!("Draws: {}", report.draws);
+    println!("Average rally: {:.2} returns", report.average_rally);
+}
@@ -0,0 +1,51 @@
+// Drives the pong environment over stdin/stdout with one JSON message per line, so a
+// trainer in another language can run it as a subprocess:
+//
+//   {"reset": 42}    -> {"observation": {...}}
+//   {"step": "up"}   -> {"observation": {...}, "reward": 0.0, "done": false}
+//
+// Actions are "up", "down" or "stay". Anything else gets back {"error": "..."}
+use std::io::{self, BufRead, Write};
+
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use pong::env::{Action, Observation, PongEnv};
+
+#[derive(Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum Request {
+    Reset(u64),
+    Step(Action),
+}
+
+#[derive(Serialize)]
+struct Reset {
+    observation: Observation,
+}
+
+fn main() -> io::Result<()> {
+    let mut env = PongEnv::new(0);
+    let stdout = io::stdout();
+    let mut out = stdout.lock();
+
+    for line in io::stdin().lock().lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let response = match serde_json::from_str::<Request>(&line) {
+            Ok(Request::Reset(seed)) => json!(Reset {
+                observation: env.reset(seed)
+            }),
+            Ok(Request::Step(action)) => json!(env.step(action)),
+            Err(error) => json!({ "error": error.to_string() }),
+        };
+
+        writeln!(out, "{}", response)?;
+        out.flush()?;
+    }
+
+    Ok(())
+}
@@ -1,4 +1,8 @@
 use bevy::prelude::*;
+use common::title::GameScreen;
+use rand::rngs::StdRng;
+
+pub use common::components::{Contact, LastContact, Velocity};
 
 #[derive(Component)]
 pub struct Ball;
@@ -10,21 +14,31 @@ pub struct PlayerPaddle;
 pub struct OpponentPaddle;
 
 #[derive(Component)]
-pub struct Velocity(pub Vec3);
+pub struct Wall;
 
-#[derive(Resource)]
+// Anything the ball bounces off. Walls use their transform's scale as their size,
+// paddles are always PADDLE_WIDTH x PADDLE_HEIGHT
+#[derive(Component)]
+pub struct Collider;
+
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum Collision {
+    Left,
+    Right,
+    Top,
+    Bottom,
+}
+
+#[derive(Resource, Default)]
 pub struct Score {
     pub player: u32,
     pub opponent: u32,
 }
 
-impl Default for Score {
-    fn default() -> Self {
-        Self {
-            player: 0,
-            opponent: 0,
-        }
-    }
+// Paddle hits since the last point, used to speed the ball up as a rally goes on
+#[derive(Resource, Default)]
+pub struct Rally {
+    pub hits: u32,
 }
 
 pub enum Scorer {
@@ -32,11 +46,59 @@ pub enum Scorer {
     Player
 }
 
+// A point was scored, ending a rally of `rally` paddle returns
 #[derive(Event)]
-pub struct Scored(pub Scorer);
+pub struct Scored {
+    pub scorer: Scorer,
+    pub rally: u32,
+}
+
+// The ball bounced off a paddle, near `point`. `returned` is true if it came off the front
+// face and is heading back across the court
+#[derive(Event)]
+pub struct BallHitPaddle {
+    pub ball: Entity,
+    pub paddle: Entity,
+    pub point: Vec2,
+    pub returned: bool,
+}
+
+// The ball bounced off a wall at `point`. `normal` is the direction of the face it hit
+#[derive(Event)]
+pub struct WallBounce {
+    pub ball: Entity,
+    pub wall: Entity,
+    pub point: Vec2,
+    pub normal: Vec2,
+}
+
+// All gameplay randomness comes from here so a seeded game always plays out the same
+#[derive(Resource)]
+pub struct GameRng(pub StdRng);
 
 #[derive(Component)]
 pub struct PlayerScoreboard;
 
 #[derive(Component)]
-pub struct OpponentScoreboard;
\ No newline at end of file
+pub struct OpponentScoreboard;
+
+// Which screen the windowed game is showing. The headless game doesn't use this
+#[derive(States, Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Screen {
+    #[default]
+    Title,
+    // The computer playing itself while nobody's around
+    Demo,
+    Playing,
+    // Typing a name in for a new high score
+    NameEntry,
+    Leaderboard,
+}
+
+impl GameScreen for Screen {
+    const TITLE: Self = Screen::Title;
+    const DEMO: Self = Screen::Demo;
+    const PLAYING: Self = Screen::Playing;
+    const NAME_ENTRY: Self = Screen::NameEntry;
+    const LEADERBOARD: Self = Screen::Leaderboard;
+}
@@ -0,0 +1,153 @@
+use bevy::{input::gamepad::GamepadAxisType, prelude::*};
+
+use crate::ai::AiKind;
+use crate::components::{Ball, PlayerPaddle, Velocity};
+use crate::playfield::Playfield;
+use crate::PADDLE_SPEED;
+
+pub use common::controller::{ControllerInput, PaddleController};
+
+// What a controller gets to see of the game when it decides how to move. Controllers steer
+// with a number from -1 (full speed down) to 1 (full speed up)
+#[derive(Debug, Clone, Copy)]
+pub struct PaddleView {
+    pub paddle: Vec3,
+    pub ball_position: Vec3,
+    pub ball_velocity: Vec3,
+    // 1.0 for the left paddle (which returns the ball to the right), -1.0 for the right one
+    pub facing: f32,
+    pub playfield: Playfield,
+}
+
+pub type Controller = common::controller::Controller<PaddleView, f32>;
+pub type External = common::controller::External<PaddleView, f32>;
+
+// Who plays which paddle, handed out when the paddles are spawned. Insert one before the
+// game starts to change it, e.g. for two players or an AI-vs-AI demo
+#[derive(Resource)]
+pub struct ControllerSetup {
+    pub left: Option<Controller>,
+    pub right: Option<Controller>,
+}
+
+impl ControllerSetup {
+    pub fn new(
+        left: impl PaddleController<View = PaddleView, Command = f32>,
+        right: impl PaddleController<View = PaddleView, Command = f32>,
+    ) -> Self {
+        Self {
+            left: Some(Controller::new(left)),
+            right: Some(Controller::new(right)),
+        }
+    }
+}
+
+impl Default for ControllerSetup {
+    // One player against the computer
+    fn default() -> Self {
+        Self::new(Keyboard::WASD, AiKind::Follow)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Keyboard {
+    pub up: KeyCode,
+    pub down: KeyCode,
+}
+
+impl Keyboard {
+    pub const WASD: Keyboard = Keyboard {
+        up: KeyCode::KeyW,
+        down: KeyCode::KeyS,
+    };
+    pub const ARROWS: Keyboard = Keyboard {
+        up: KeyCode::ArrowUp,
+        down: KeyCode::ArrowDown,
+    };
+}
+
+impl PaddleController for Keyboard {
+    type View = PaddleView;
+    type Command = f32;
+
+    fn steer(&mut self, _view: &PaddleView, input: &ControllerInput) -> f32 {
+        if input.keys.pressed(self.up) {
+            1.
+        } else if input.keys.pressed(self.down) {
+            -1.
+        } else {
+            0.
+        }
+    }
+}
+
+// The left stick or d-pad of a gamepad, by the order they were connected in (0 is the first)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GamepadStick(pub usize);
+
+impl PaddleController for GamepadStick {
+    type View = PaddleView;
+    type Command = f32;
+
+    fn steer(&mut self, _view: &PaddleView, input: &ControllerInput) -> f32 {
+        let Some(gamepad) = input.gamepads.iter().nth(self.0) else {
+            return 0.;
+        };
+
+        let pressed = |button| input.gamepad_buttons.pressed(GamepadButton::new(gamepad, button));
+        if pressed(GamepadButtonType::DPadUp) {
+            1.
+        } else if pressed(GamepadButtonType::DPadDown) {
+            -1.
+        } else {
+            input
+                .gamepad_axes
+                .get(GamepadAxis::new(gamepad, GamepadAxisType::LeftStickY))
+                .unwrap_or(0.)
+        }
+    }
+}
+
+// Plays back recorded steering one tick at a time, then stands still
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Replay {
+    steering: Vec<f32>,
+    frame: usize,
+}
+
+impl Replay {
+    pub fn new(steering: Vec<f32>) -> Self {
+        Self { steering, frame: 0 }
+    }
+}
+
+impl PaddleController for Replay {
+    type View = PaddleView;
+    type Command = f32;
+
+    fn steer(&mut self, _view: &PaddleView, _input: &ControllerInput) -> f32 {
+        let steer = self.steering.get(self.frame).copied().unwrap_or(0.);
+        self.frame += 1;
+        steer
+    }
+}
+
+pub fn drive_paddles(
+    input: ControllerInput,
+    ball_query: Query<(&Transform, &Velocity), With<Ball>>,
+    mut paddle_query: Query<(&mut Velocity, &Transform, &mut Controller, Has<PlayerPaddle>), Without<Ball>>,
+    playfield: Res<Playfield>,
+) {
+    let (ball_transform, ball_velocity) = ball_query.single();
+
+    for (mut velocity, transform, mut controller, is_player) in paddle_query.iter_mut() {
+        let view = PaddleView {
+            paddle: transform.translation,
+            ball_position: ball_transform.translation,
+            ball_velocity: ball_velocity.0,
+            facing: if is_player { 1. } else { -1. },
+            playfield: *playfield,
+        };
+        velocity.0.y = controller.0.steer(&view, &input).clamp(-1., 1.) * PADDLE_SPEED;
+    }
+}
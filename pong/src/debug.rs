@@ -0,0 +1,44 @@
+use std::marker::PhantomData;
+
+use bevy::prelude::*;
+use common::debug::{DebugInfo, DebugLines, DebugOverlay, COLLIDER_COLOR, PADDLE_COLOR};
+
+use crate::components::{Ball, Collider, Screen, Wall};
+use crate::{BALL_RADIUS, PADDLE_HEIGHT, PADDLE_WIDTH};
+
+// The debug overlay, with the box of every wall and paddle and the screen pong is on
+pub struct DebugOverlayPlugin;
+
+impl Plugin for DebugOverlayPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins(common::debug::DebugOverlayPlugin::<Ball> {
+            ball_radius: BALL_RADIUS,
+            text_position: Style {
+                bottom: Val::Px(10.0),
+                right: Val::Px(10.0),
+                ..default()
+            },
+            balls: PhantomData,
+        })
+        .add_systems(
+            Update,
+            (draw_colliders, show_screen.in_set(DebugInfo)).run_if(resource_equals(DebugOverlay(true))),
+        );
+    }
+}
+
+fn draw_colliders(mut gizmos: Gizmos, colliders: Query<(&Transform, Has<Wall>), With<Collider>>) {
+    for (transform, is_wall) in colliders.iter() {
+        let position = transform.translation.truncate();
+        if is_wall {
+            gizmos.rect_2d(position, 0., transform.scale.truncate(), COLLIDER_COLOR);
+        } else {
+            gizmos.rect_2d(position, 0., Vec2::new(PADDLE_WIDTH, PADDLE_HEIGHT), PADDLE_COLOR);
+        }
+    }
+}
+
+fn show_screen(screen: Option<Res<State<Screen>>>, mut lines: ResMut<DebugLines>) {
+    let screen = screen.map_or("-".to_string(), |screen| format!("{:?}", screen.get()));
+    lines.0.push(format!("Screen: {}", screen));
+}
@@ -0,0 +1,154 @@
+use std::time::Duration;
+
+use bevy::{input::InputPlugin, prelude::*, time::TimeUpdateStrategy};
+use serde::{Deserialize, Serialize};
+
+use crate::ai::AiKind;
+use crate::components::{Ball, OpponentPaddle, PlayerPaddle, Score, Velocity};
+use crate::controller::{ControllerSetup, External};
+use crate::{PongPlugin, TICK};
+
+// Every step moves the game forward one tick, however fast it's actually running
+const STEP_DURATION: Duration = TICK;
+// Give up on an episode if nobody has scored after two minutes of game time
+const MAX_EPISODE_STEPS: u32 = 60 * 120;
+
+// What the agent does with the player (left) paddle for one step
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Action {
+    Up,
+    Down,
+    Stay,
+}
+
+impl Action {
+    // As steering for a `PaddleController`
+    pub fn steer(&self) -> f32 {
+        match self {
+            Action::Up => 1.,
+            Action::Down => -1.,
+            Action::Stay => 0.,
+        }
+    }
+}
+
+// Everything the agent can see, in world units (the playfield is centered on the origin)
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct Observation {
+    pub ball_position: [f32; 2],
+    pub ball_velocity: [f32; 2],
+    pub player_y: f32,
+    pub player_velocity: f32,
+    pub opponent_y: f32,
+    pub opponent_velocity: f32,
+}
+
+impl Observation {
+    // Builds an observation from the (position, velocity) of the ball and both paddles
+    pub fn new(ball: (Vec3, Vec3), player: (Vec3, Vec3), opponent: (Vec3, Vec3)) -> Self {
+        Self {
+            ball_position: ball.0.truncate().to_array(),
+            ball_velocity: ball.1.truncate().to_array(),
+            player_y: player.0.y,
+            player_velocity: player.1.y,
+            opponent_y: opponent.0.y,
+            opponent_velocity: opponent.1.y,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct Step {
+    pub observation: Observation,
+    // 1.0 when the player scores, -1.0 when the opponent does
+    pub reward: f32,
+    // An episode is a single point
+    pub done: bool,
+}
+
+// Pong as a reinforcement learning environment. The agent plays the left paddle against
+// the built-in opponent, with the real game systems running headless as fast as possible.
+pub struct PongEnv {
+    app: App,
+    agent: External,
+    steps: u32,
+}
+
+impl PongEnv {
+    pub fn new(seed: u64) -> Self {
+        let mut env = Self {
+            app: App::new(),
+            agent: External::default(),
+            steps: 0,
+        };
+        env.reset(seed);
+        env
+    }
+
+    // Starts a fresh game. The same seed always plays out the same way for the same actions
+    pub fn reset(&mut self, seed: u64) -> Observation {
+        self.agent = External::default();
+        self.app = headless_app(PongPlugin { seed: Some(seed) });
+        self.app
+            .insert_resource(ControllerSetup::new(self.agent.clone(), AiKind::Follow));
+        self.app.finish();
+        self.app.cleanup();
+        self.steps = 0;
+        // Runs the startup systems that spawn the ball and paddles
+        self.app.update();
+        self.observe()
+    }
+
+    pub fn step(&mut self, action: Action) -> Step {
+        let before = score(self.app.world());
+
+        self.agent.set(action.steer());
+        self.app.update();
+        self.steps += 1;
+
+        let after = score(self.app.world_mut());
+        let reward = (after.0 - before.0) as f32 - (after.1 - before.1) as f32;
+
+        Step {
+            observation: self.observe(),
+            reward,
+            done: reward != 0. || self.steps >= MAX_EPISODE_STEPS,
+        }
+    }
+
+    pub fn observe(&mut self) -> Observation {
+        let world = self.app.world_mut();
+        Observation::new(
+            motion::<Ball>(world),
+            motion::<PlayerPaddle>(world),
+            motion::<OpponentPaddle>(world),
+        )
+    }
+}
+
+// The pong game without a window, advancing a fixed 60 FPS frame per update.
+// More plugins can be added before it's first updated
+pub fn headless_app(game: PongPlugin) -> App {
+    let mut app = App::new();
+    app.add_plugins((MinimalPlugins, InputPlugin))
+        .insert_resource(TimeUpdateStrategy::ManualDuration(STEP_DURATION))
+        // Nothing gets rendered, but the game still creates meshes and materials for its entities
+        .init_resource::<Assets<Mesh>>()
+        .init_resource::<Assets<ColorMaterial>>()
+        .add_plugins(game);
+    app
+}
+
+// Position and velocity of the one entity marked with `T`
+fn motion<T: Component>(world: &mut World) -> (Vec3, Vec3) {
+    let (transform, velocity) = world
+        .query_filtered::<(&Transform, &Velocity), With<T>>()
+        .single(world);
+    (transform.translation, velocity.0)
+}
+
+fn score(world: &World) -> (u32, u32) {
+    let score = world.resource::<Score>();
+    (score.player, score.opponent)
+}
@@ -0,0 +1,64 @@
+use std::marker::PhantomData;
+use std::path::PathBuf;
+
+use bevy::prelude::*;
+use common::highscore::GameFinished;
+
+use crate::components::{Score, Scored, Scorer, Screen};
+use crate::MATCH_POINTS;
+
+pub use common::highscore::HighScores;
+
+// Points for winning a point, and for each return in the rally that won it
+const POINT_SCORE: u32 = 100;
+const RETURN_SCORE: u32 = 10;
+
+// Ends matches started from the title screen at MATCH_POINTS, offers a place in the mode's
+// high-score table if the player did well enough, and shows the table from the title screen.
+// The player's score is POINT_SCORE for each point they win, plus RETURN_SCORE for each
+// return in the rally it ended.
+pub struct HighScorePlugin {
+    // Where to save the tables, `None` to keep them only until the game exits
+    pub path: Option<PathBuf>,
+    // The table to use
+    pub mode: String,
+    // Whether the player's games are recorded at all
+    pub record: bool,
+}
+
+impl Plugin for HighScorePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins(common::highscore::HighScorePlugin::<Screen> {
+            path: self.path.clone(),
+            mode: self.mode.clone(),
+            record: self.record,
+            screens: PhantomData,
+        })
+        .init_resource::<PlayerScore>()
+        .add_systems(OnEnter(Screen::Playing), |mut score: ResMut<PlayerScore>| score.0 = 0)
+        .add_systems(
+            Update,
+            (tally_player_score, end_match)
+                .chain()
+                .run_if(in_state(Screen::Demo).or_else(in_state(Screen::Playing))),
+        );
+    }
+}
+
+// The player's score in the current match
+#[derive(Resource, Default)]
+struct PlayerScore(u32);
+
+fn tally_player_score(mut events: EventReader<Scored>, mut score: ResMut<PlayerScore>) {
+    for event in events.read() {
+        if matches!(event.scorer, Scorer::Player) {
+            score.0 += POINT_SCORE + RETURN_SCORE * event.rally;
+        }
+    }
+}
+
+fn end_match(score: Res<Score>, player_score: Res<PlayerScore>, mut finished: EventWriter<GameFinished>) {
+    if score.player >= MATCH_POINTS || score.opponent >= MATCH_POINTS {
+        finished.send(GameFinished { score: player_score.0 });
+    }
+}
@@ -0,0 +1,76 @@
+use std::marker::PhantomData;
+
+use bevy::prelude::*;
+use common::juice::{JuiceStyle, Juiced, Shake};
+
+use crate::components::{Ball, BallHitPaddle, OpponentPaddle, PlayerPaddle, Scored, Velocity, WallBounce};
+use crate::{BALL_SPEED, MAX_BALL_SPEED};
+
+pub use common::juice::JuiceSettings;
+
+// Game feel: the camera shakes with every impact, harder the faster the ball is going, the
+// paddles squash when the ball hits them and the ball stretches as it flies. Pong has no
+// hit-stop.
+pub struct JuicePlugin {
+    pub settings: JuiceSettings,
+}
+
+impl Plugin for JuicePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins(common::juice::JuicePlugin::<Ball> {
+            settings: self.settings,
+            style: JuiceStyle {
+                stretch: 0.15,
+                speed: BALL_SPEED,
+                squash: 0.3,
+                squash_axis: Vec2::X,
+            },
+            balls: PhantomData,
+        })
+        .add_systems(Update, add_paddle_juice)
+        .add_systems(Update, (shake_on_impact, squash_paddle));
+    }
+}
+
+#[allow(clippy::type_complexity)]
+fn add_paddle_juice(mut commands: Commands, added: Query<Entity, Or<(Added<PlayerPaddle>, Added<OpponentPaddle>)>>) {
+    for entity in added.iter() {
+        commands.entity(entity).insert(Juiced::default());
+    }
+}
+
+fn shake_on_impact(
+    mut paddle_hits: EventReader<BallHitPaddle>,
+    mut wall_bounces: EventReader<WallBounce>,
+    mut scored: EventReader<Scored>,
+    balls: Query<&Velocity, With<Ball>>,
+    mut shake: ResMut<Shake>,
+) {
+    for event in paddle_hits.read() {
+        if !event.returned {
+            shake.add(0.05);
+            continue;
+        }
+        // Returns shake harder as the rally speeds the ball up
+        let speed = balls.get(event.ball).map_or(BALL_SPEED, |velocity| velocity.0.length());
+        shake.add(0.1 + 0.25 * ((speed - BALL_SPEED) / (MAX_BALL_SPEED - BALL_SPEED)).clamp(0., 1.));
+    }
+    for _ in wall_bounces.read() {
+        shake.add(0.05);
+    }
+    for _ in scored.read() {
+        shake.add(0.5);
+    }
+}
+
+#[allow(clippy::type_complexity)]
+fn squash_paddle(
+    mut paddle_hits: EventReader<BallHitPaddle>,
+    mut paddles: Query<&mut Juiced, Or<(With<PlayerPaddle>, With<OpponentPaddle>)>>,
+) {
+    for event in paddle_hits.read() {
+        if let Ok(mut juiced) = paddles.get_mut(event.paddle) {
+            juiced.squash();
+        }
+    }
+}
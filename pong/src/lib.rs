@@ -0,0 +1,359 @@
+use std::time::Duration;
+
+use bevy::{
+    prelude::*,
+    sprite::{MaterialMesh2dBundle, Mesh2dHandle},
+    math::bounding::Aabb2d,
+};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+pub mod ai;
+pub mod arena;
+pub mod components;
+pub mod controller;
+pub mod debug;
+pub mod env;
+pub mod highscore;
+pub mod juice;
+pub mod particles;
+pub mod physics;
+pub mod playfield;
+pub mod remote;
+pub mod timescale;
+pub mod title;
+pub mod trail;
+
+use arena::*;
+use components::*;
+use controller::*;
+use physics::*;
+use playfield::*;
+
+pub const PADDLE_WIDTH: f32 = 15.;
+pub const PADDLE_HEIGHT: f32 = 100.;
+pub const PADDLE_PAD: f32 = PADDLE_WIDTH / 2. + 10.;
+pub const BALL_RADIUS: f32 = 8.;
+pub const BALL_SPEED: f32 = 400.;
+pub const BALL_SPEED_STEP: f32 = 20.;
+pub const MAX_BALL_SPEED: f32 = 900.;
+pub const MAX_BOUNCE_ANGLE: f32 = std::f32::consts::FRAC_PI_3;
+pub const PADDLE_SPIN_ANGLE: f32 = std::f32::consts::FRAC_PI_8;
+pub const PADDLE_SPEED: f32 = 200.;
+// Points it takes to win a match on the title screen's games
+pub const MATCH_POINTS: u32 = 7;
+// How long a tick of the simulation lasts, 60 to the second
+pub const TICK: Duration = Duration::from_nanos(16_666_667);
+
+// Both run every FixedUpdate tick
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub enum PongSet {
+    // Deciding how the paddles move this tick (keyboard, AI, bots...)
+    Input,
+    // Moving everything, resolving collisions and scoring
+    Simulation,
+}
+
+// The pong game itself, without any windowing or UI so it can also run headless
+#[derive(Default)]
+pub struct PongPlugin {
+    // Seeds the serve directions, random if not set
+    pub seed: Option<u64>,
+}
+
+impl Plugin for PongPlugin {
+    fn build(&self, app: &mut App) {
+        let playfield = Playfield::default();
+        let rng = match self.seed {
+            Some(seed) => StdRng::seed_from_u64(seed),
+            None => StdRng::from_entropy(),
+        };
+
+        app.insert_resource(playfield)
+            .insert_resource(Arena::classic(&playfield))
+            .insert_resource(Score::default())
+            .insert_resource(Rally::default())
+            .init_resource::<LastContact>()
+            .insert_resource(GameRng(rng))
+            .insert_resource(Time::<Fixed>::from_duration(TICK))
+            .init_resource::<ControllerSetup>()
+            .add_event::<Scored>()
+            .add_event::<BallHitPaddle>()
+            .add_event::<WallBounce>()
+            .configure_sets(FixedUpdate, (PongSet::Input, PongSet::Simulation).chain())
+            .add_systems(Startup, (setup, start_match))
+            .add_systems(FixedUpdate, drive_paddles.in_set(PongSet::Input))
+            .add_systems(
+                FixedUpdate,
+                (
+                    normalize_ball_speed,
+                    update_position,
+                    clamp_paddles,
+                    check_collisions,
+                    check_score_event,
+                ).chain().in_set(PongSet::Simulation),
+            );
+    }
+}
+
+fn setup(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    playfield: Res<Playfield>,
+    arena: Res<Arena>,
+) {
+    spawn_arena(&mut commands, &arena);
+
+    // Scoreboard "wall"
+    commands.spawn(
+        MaterialMesh2dBundle {
+            mesh: Mesh2dHandle(meshes.add(Rectangle::new(playfield.width, 1.0))),
+            material: materials.add(Color::WHITE),
+            transform: Transform::from_translation(Vec3::new(0.0, playfield.ceiling(), 0.0)),
+            ..default()
+        },
+    );
+}
+
+// Clears the score and puts a fresh ball and paddles on the field, with the paddles
+// played by whoever is in `ControllerSetup`
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
+pub fn start_match(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    playfield: Res<Playfield>,
+    mut rng: ResMut<GameRng>,
+    mut controllers: ResMut<ControllerSetup>,
+    mut score: ResMut<Score>,
+    mut rally: ResMut<Rally>,
+    previous: Query<Entity, Or<(With<Ball>, With<PlayerPaddle>, With<OpponentPaddle>)>>,
+) {
+    for entity in previous.iter() {
+        commands.entity(entity).despawn();
+    }
+    *score = Score::default();
+    *rally = Rally::default();
+
+    let ball_y: f32 = rng.0.gen();
+
+    // Ball
+    commands.spawn((
+        MaterialMesh2dBundle {
+            mesh: Mesh2dHandle(meshes.add(Circle {
+                radius: BALL_RADIUS,
+            })),
+            material: materials.add(Color::WHITE),
+            transform: Transform::from_translation(Vec3::new(
+                0.0,
+                -playfield.scoreboard_height(),
+                0.0,
+            )),
+            ..default()
+        },
+        Ball,
+        Velocity(Vec3::new(-BALL_SPEED, BALL_SPEED / 3. + BALL_SPEED * ball_y, 0.0)),
+    ));
+
+    // Player
+    let mut player = commands.spawn((
+        MaterialMesh2dBundle {
+            mesh: Mesh2dHandle(meshes.add(Rectangle::new(PADDLE_WIDTH, PADDLE_HEIGHT))),
+            material: materials.add(Color::WHITE),
+            transform: Transform::from_translation(Vec3::new(
+                playfield.left() + PADDLE_PAD,
+                -playfield.scoreboard_height(),
+                0.0,
+            )),
+            ..default()
+        },
+        PlayerPaddle,
+        Collider,
+        Velocity(Vec3::ZERO),
+    ));
+    if let Some(controller) = controllers.left.take() {
+        player.insert(controller);
+    }
+
+    // Opponent
+    let mut opponent = commands.spawn((
+        MaterialMesh2dBundle {
+            mesh: Mesh2dHandle(meshes.add(Rectangle::new(15.0, 100.0))),
+            material: materials.add(Color::WHITE),
+            transform: Transform::from_translation(Vec3::new(
+                playfield.right() - PADDLE_PAD,
+                -playfield.scoreboard_height(),
+                0.0,
+            )),
+            ..default()
+        },
+        OpponentPaddle,
+        Collider,
+        Velocity(Vec3::ZERO),
+    ));
+    if let Some(controller) = controllers.right.take() {
+        opponent.insert(controller);
+    }
+}
+
+fn update_position(time: Res<Time>, mut query: Query<(&Velocity, &mut Transform)>) {
+    for (velocity, mut transform) in query.iter_mut() {
+        transform.translation += velocity.0 * time.delta_seconds();
+    }
+}
+
+fn normalize_ball_speed(mut query: Query<&mut Velocity, With<Ball>>, rally: Res<Rally>) {
+    let mut ball_velocity = query.single_mut();
+    let speed = rally_speed(rally.hits);
+
+    if ball_velocity.0.length() != speed {
+        ball_velocity.0 = ball_velocity.0.normalize() * speed;
+    }
+}
+
+#[allow(clippy::type_complexity)]
+fn clamp_paddles(
+    mut query: Query<(&mut Velocity, &mut Transform), Or<(With<PlayerPaddle>, With<OpponentPaddle>)>>,
+    playfield: Res<Playfield>,
+) {
+    for (mut velocity, mut transform) in query.iter_mut() {
+        if transform.translation.y + PADDLE_HEIGHT / 2. >= playfield.ceiling() {
+            // Prevent the paddle from going off the top of the screen
+            velocity.0.y = 0.;
+            transform.translation.y = playfield.ceiling() - PADDLE_HEIGHT / 2.;
+        } else if transform.translation.y - PADDLE_HEIGHT / 2. <= playfield.bottom() {
+            // Prevent the paddle from going off the bottom of the screen
+            velocity.0.y = 0.;
+            transform.translation.y = playfield.bottom() + PADDLE_HEIGHT / 2.;
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
+fn check_collisions(
+    mut ball_query: Query<(Entity, &mut Velocity, &mut Transform), With<Ball>>,
+    collider_query: Query<(Entity, &Transform, Option<&Velocity>, Option<&PlayerPaddle>, Option<&OpponentPaddle>), (With<Collider>, Without<Ball>)>,
+    mut events: EventWriter<Scored>,
+    mut paddle_hits: EventWriter<BallHitPaddle>,
+    mut wall_bounces: EventWriter<WallBounce>,
+    mut rally: ResMut<Rally>,
+    mut last_contact: ResMut<LastContact>,
+    playfield: Res<Playfield>,
+) {
+    let (ball, mut ball_velocity, mut ball_transform) = ball_query.single_mut();
+
+    let ball_x = ball_transform.translation.x;
+
+    if ball_x - BALL_RADIUS <= playfield.left() {
+        // Player scores
+        info!("Opponent scores!");
+        events.send(Scored {
+            scorer: Scorer::Opponent,
+            rally: rally.hits,
+        });
+        return
+    } else if ball_x + BALL_RADIUS >= playfield.right() {
+        // Opponent scores
+        info!("Player scores!");
+        events.send(Scored {
+            scorer: Scorer::Player,
+            rally: rally.hits,
+        });
+        return
+    }
+
+    for (entity, transform, maybe_velocity, maybe_player, maybe_opponent) in collider_query.iter() {
+        let facing = if maybe_player.is_some() {
+            1.
+        } else if maybe_opponent.is_some() {
+            -1.
+        } else {
+            // Bounce off a wall
+            let wall = Aabb2d::new(transform.translation.truncate(), transform.scale.truncate() / 2.);
+            if let Some((position, velocity)) = wall_collision(ball_transform.translation, ball_velocity.0, wall) {
+                // Only a bounce if the ball was moving into the wall
+                let normal = (velocity - ball_velocity.0).truncate().normalize_or_zero();
+                if normal != Vec2::ZERO {
+                    let point = position.truncate() - normal * BALL_RADIUS;
+                    wall_bounces.send(WallBounce {
+                        ball,
+                        wall: entity,
+                        point,
+                        normal,
+                    });
+                    last_contact.0 = Some(Contact { point, normal });
+                }
+                ball_transform.translation = position;
+                ball_velocity.0 = velocity;
+            }
+            continue;
+        };
+
+        // Bounce off a paddle. Only hits on the front face return the ball (angled by where
+        // it hit and how the paddle was moving), the other faces just knock it aside
+        let hit = paddle_collision(
+            ball_transform.translation,
+            ball_velocity.0,
+            transform.translation,
+            maybe_velocity.map_or(0., |velocity| velocity.0.y),
+            facing,
+            rally_speed(rally.hits + 1),
+        );
+        if let Some(hit) = hit {
+            if hit.returned {
+                rally.hits += 1;
+            }
+            if hit.velocity != ball_velocity.0 {
+                paddle_hits.send(BallHitPaddle {
+                    ball,
+                    paddle: entity,
+                    point: hit.position.truncate(),
+                    returned: hit.returned,
+                });
+                // The ball is pushed out the way it was hit, or straight off the front face
+                // when it's caught just touching
+                let normal = (hit.position - ball_transform.translation)
+                    .truncate()
+                    .try_normalize()
+                    .unwrap_or(Vec2::X * facing);
+                last_contact.0 = Some(Contact {
+                    point: hit.position.truncate() - normal * BALL_RADIUS,
+                    normal,
+                });
+            }
+            ball_transform.translation = hit.position;
+            ball_velocity.0 = hit.velocity;
+        }
+    }
+}
+
+fn check_score_event(
+    mut ball: Query<(&mut Transform, &mut Velocity), With<Ball>>,
+    mut score: ResMut<Score>,
+    mut rally: ResMut<Rally>,
+    mut rng: ResMut<GameRng>,
+    mut events: EventReader<Scored>,
+) {
+    let (mut ball_transform, mut ball_velocity) = ball.single_mut();
+
+    let ball_y: f32 = rng.0.gen();
+
+    for event in events.read() {
+        // Every point starts a new rally at the base speed
+        rally.hits = 0;
+        match event.scorer {
+            Scorer::Player => {
+                score.player += 1;
+                ball_transform.translation = Vec3::ZERO;
+                ball_velocity.0 = Vec3::new(-BALL_SPEED, BALL_SPEED / 3. + BALL_SPEED * ball_y, 0.0);
+            }
+            Scorer::Opponent => {
+                score.opponent += 1;
+                ball_transform.translation = Vec3::ZERO;
+                ball_velocity.0 = Vec3::new(BALL_SPEED, -BALL_SPEED / 3. - BALL_SPEED * ball_y, 0.0);
+            }
+        }
+        info!("Score: {} - {}", score.player, score.opponent);
+    }
+}
+
@@ -1,30 +1,30 @@
-use bevy::{
-    prelude::*,
-    sprite::{MaterialMesh2dBundle, Mesh2dHandle},
-};
-use rand::Rng;
-
-mod components;
-
-use components::*;
-
-const SCREEN_WIDTH: f32 = 1280.;
-const SCREEN_HEIGHT: f32 = 720.;
-const PADDLE_WIDTH: f32 = 15.;
-const PADDLE_HEIGHT: f32 = 100.;
-const PADDLE_PAD: f32 = PADDLE_WIDTH / 2. + 10.;
-const BALL_RADIUS: f32 = 8.;
-const BALL_SPEED: f32 = 400.;
-const PADDLE_SPEED: f32 = 200.;
-const SCOREBOARD_HEIGHT: f32 = SCREEN_HEIGHT * 0.05;
+use bevy::prelude::*;
+use common::settings::{Settings, SettingsPlugin};
+
+use pong::ai::AiKind;
+use pong::components::*;
+use pong::controller::{ControllerSetup, GamepadStick, Keyboard};
+use pong::debug::DebugOverlayPlugin;
+use pong::highscore::{HighScorePlugin, HighScores};
+use pong::juice::{JuicePlugin, JuiceSettings};
+use pong::particles::ParticlesPlugin;
+use pong::playfield::*;
+use pong::remote::RemoteControlPlugin;
+use pong::timescale::TimeScalePlugin;
+use pong::title::TitlePlugin;
+use pong::trail::TrailPlugin;
+use pong::PongPlugin;
 
 fn main() {
-    App::new()
+    let playfield = Playfield::default();
+
+    let mut app = App::new();
+    app
         .add_plugins(
             DefaultPlugins
                 .set(WindowPlugin {
                     primary_window: Some(Window {
-                        resolution: (SCREEN_WIDTH, SCREEN_HEIGHT).into(),
+                        resolution: (playfield.width, playfield.height).into(),
                         title: "Pong".to_string(),
                         ..default()
                     }),
@@ -32,264 +32,85 @@ fn main() {
                 })
                 .build(),
         )
-        .insert_resource(Score::default())
-        .add_event::<Scored>()
-        .add_systems(Startup, (setup, create_scoreboard).chain())
-        .add_systems(
-            Update,
-            (
-                player_movement,
-                normalize_ball_speed,
-                update_position,
-                opponent_movement,
-                check_collisions,
-                check_score_event,
-                update_scoreboard,
-            ).chain(),
-        )
-        .run();
-}
-
-fn setup(
-    mut commands: Commands,
-    mut meshes: ResMut<Assets<Mesh>>,
-    mut materials: ResMut<Assets<ColorMaterial>>,
-) {
-    commands.spawn(Camera2dBundle::default());
-
-    let mut rng = rand::thread_rng();
-    let ball_y: f32 = rng.gen();
-
-    // Ball
-    commands.spawn((
-        MaterialMesh2dBundle {
-            mesh: Mesh2dHandle(meshes.add(Circle {
-                radius: BALL_RADIUS,
-            })),
-            material: materials.add(Color::WHITE),
-            transform: Transform::from_translation(Vec3::new(
-                0.0,
-                -SCOREBOARD_HEIGHT,
-                0.0,
-            )),
-            ..default()
-        },
-        Ball,
-        Velocity(Vec3::new(-BALL_SPEED, BALL_SPEED / 3. + BALL_SPEED * ball_y, 0.0)),
-    ));
-
-    // Player
-    commands.spawn((
-        MaterialMesh2dBundle {
-            mesh: Mesh2dHandle(meshes.add(Rectangle::new(PADDLE_WIDTH, PADDLE_HEIGHT))),
-            material: materials.add(Color::WHITE),
-            transform: Transform::from_translation(Vec3::new(
-                -SCREEN_WIDTH / 2. + PADDLE_PAD,
-                -SCOREBOARD_HEIGHT,
-                0.0,
-            )),
-            ..default()
-        },
-        PlayerPaddle,
-        Velocity(Vec3::ZERO),
-    ));
-
-    // Opponent
-    commands.spawn((
-        MaterialMesh2dBundle {
-            mesh: Mesh2dHandle(meshes.add(Rectangle::new(15.0, 100.0))),
-            material: materials.add(Color::WHITE),
-            transform: Transform::from_translation(Vec3::new(
-                SCREEN_WIDTH / 2. - PADDLE_PAD,
-                -SCOREBOARD_HEIGHT,
-                0.0,
-            )),
-            ..default()
-        },
-        OpponentPaddle,
-        Velocity(Vec3::ZERO),
-    ));
-
-    // Scoreboard "wall"
-    commands.spawn(
-        MaterialMesh2dBundle {
-            mesh: Mesh2dHandle(meshes.add(Rectangle::new(SCREEN_WIDTH, 1.0))),
-            material: materials.add(Color::WHITE),
-            transform: Transform::from_translation(Vec3::new(0.0, SCREEN_HEIGHT / 2.0 - SCOREBOARD_HEIGHT, 0.0)),
-            ..default()
-        },
-    );
-}
+        .add_plugins(PongPlugin::default())
+        .add_plugins(TitlePlugin {
+            players: controller_setup,
+        })
+        .add_plugins(ParticlesPlugin)
+        .add_plugins(TrailPlugin)
+        .add_plugins(DebugOverlayPlugin)
+        .add_plugins(TimeScalePlugin)
+        .add_plugins(SettingsPlugin {
+            path: Settings::default_path("pong"),
+            // Turns off screen shake and the other effects for this run
+            reduce_motion: std::env::args().any(|arg| arg == "--reduce-motion"),
+        })
+        .add_plugins(JuicePlugin {
+            settings: JuiceSettings::default(),
+        })
+        .add_plugins(HighScorePlugin {
+            path: HighScores::default_path("pong"),
+            mode: "vs-computer".to_string(),
+            // Only games against the computer count
+            record: !std::env::args().any(|arg| arg == "--demo" || arg == "--two-player"),
+        })
+        .add_systems(Startup, (spawn_camera, create_scoreboard).chain())
+        .add_systems(Update, (toggle_fullscreen, fit_camera_to_window).chain())
+        .add_systems(Update, update_scoreboard);
 
-fn update_position(time: Res<Time>, mut query: Query<(&Velocity, &mut Transform)>) {
-    for (velocity, mut transform) in query.iter_mut() {
-        transform.translation += velocity.0 * time.delta_seconds();
+    if let Some(port) = remote_control_port() {
+        app.add_plugins(RemoteControlPlugin { port });
     }
-}
-
-fn normalize_ball_speed(mut query: Query<&mut Velocity, With<Ball>>) {
-    let mut ball_velocity = query.single_mut();
 
-    if ball_velocity.0.length() != BALL_SPEED {
-        ball_velocity.0 = ball_velocity.0.normalize() * BALL_SPEED;
-    }
+    app.run();
 }
 
-fn player_movement(
-    key: Res<ButtonInput<KeyCode>>,
-    mut query: Query<&mut Velocity, With<PlayerPaddle>>,
-) {
-    for mut velocity in query.iter_mut() {
-        if key.pressed(KeyCode::KeyW) {
-            velocity.0.y = PADDLE_SPEED;
-        } else if key.pressed(KeyCode::KeyS) {
-            velocity.0.y = -PADDLE_SPEED;
-        } else {
-            velocity.0.y = 0.;
-        }
-    }
+// `--remote-control <port>` lets bots drive the paddles over a localhost socket
+fn remote_control_port() -> Option<u16> {
+    let mut args = std::env::args().skip_while(|arg| arg != "--remote-control");
+    args.next()?;
+    let port = args.next().expect("--remote-control needs a port number");
+    Some(port.parse().expect("--remote-control port must be a number between 0 and 65535"))
 }
 
-fn opponent_movement(
-    ball_query: Query<(&Transform, &Velocity), With<Ball>>,
-    mut query: Query<(&mut Velocity, &Transform), (With<OpponentPaddle>, Without<Ball>)>,
-) {
-    let (ball_transform, ball_velocity)= ball_query.single();
-    let (mut paddle_velocity, paddle_transform) = query.single_mut();
-
-    if ball_velocity.0.x < 0. {
-        // Ball moving away - return to center
-        if paddle_transform.translation.y < 0. {
-            paddle_velocity.0.y = PADDLE_SPEED;
-        } else if paddle_transform.translation.y > 0. {
-            paddle_velocity.0.y = -PADDLE_SPEED;
-        } else {
-            paddle_velocity.0.y = 0.;
-        }
+// Who plays which paddle:
+//   (no flags)     W/S against the computer
+//   --two-player   W/S on the left against the arrow keys on the right
+//   --demo         The computer against itself
+//   --gamepad      Use gamepads instead of the keyboard for the human players
+fn controller_setup() -> ControllerSetup {
+    let flag = |name: &str| std::env::args().any(|arg| arg == name);
+    let gamepad = flag("--gamepad");
+
+    if flag("--demo") {
+        ControllerSetup::new(AiKind::Predict, AiKind::Follow)
+    } else if flag("--two-player") && gamepad {
+        ControllerSetup::new(GamepadStick(0), GamepadStick(1))
+    } else if flag("--two-player") {
+        ControllerSetup::new(Keyboard::WASD, Keyboard::ARROWS)
+    } else if gamepad {
+        ControllerSetup::new(GamepadStick(0), AiKind::Follow)
     } else {
-        // Ball moving towards - follow the ball
-        if ball_transform.translation.y < paddle_transform.translation.y {
-            paddle_velocity.0.y = -PADDLE_SPEED;
-        } else if ball_transform.translation.y > paddle_transform.translation.y {
-            paddle_velocity.0.y = PADDLE_SPEED;
-        } else {
-            paddle_velocity.0.y = 0.;
-        }
-    }
-
-}
-
-fn check_collisions(
-    mut ball_query: Query<(&mut Velocity, &mut Transform), (With<Ball>, Without<PlayerPaddle>, Without<OpponentPaddle>)>,
-    mut player_query: Query<(&mut Velocity, &mut Transform), (With<PlayerPaddle>, Without<OpponentPaddle>)>,
-    mut opponent_query: Query<&mut Transform, (With<OpponentPaddle>, Without<Ball>, Without<PlayerPaddle>)>,
-    mut events: EventWriter<Scored>,
-) {
-    let (mut ball_velocity, mut ball_transform) = ball_query.single_mut();
-    let (mut player_velocity, mut player_transform) = player_query.single_mut();
-    let mut opponent_transform = opponent_query.single_mut();
-
-    let ball_x = ball_transform.translation.x;
-    let ball_y = ball_transform.translation.y;
-
-    let player_x = player_transform.translation.x;
-    let player_y = player_transform.translation.y;
-
-    let opponent_x = opponent_transform.translation.x;
-    let opponent_y = opponent_transform.translation.y;
-
-    if ball_x - BALL_RADIUS <= -SCREEN_WIDTH / 2. {
-        // Player scores
-        println!("Opponent scores!");
-        events.send(Scored(Scorer::Opponent));
-        return
-    } else if ball_x + BALL_RADIUS >= SCREEN_WIDTH / 2. {
-        // Opponent scores
-        println!("Player scores!");
-        events.send(Scored(Scorer::Player));
-        return
-    }
-
-    if ball_y - BALL_RADIUS <= -SCREEN_HEIGHT / 2. {
-        // Bounce off the bottom wall
-        ball_velocity.0.y = -ball_velocity.0.y;
-        ball_transform.translation.y = -SCREEN_HEIGHT / 2. + BALL_RADIUS + 1.;
-    } else if ball_y + BALL_RADIUS >= SCREEN_HEIGHT / 2. - SCOREBOARD_HEIGHT {
-        // Bounce off the top wall
-        ball_velocity.0.y = -ball_velocity.0.y;
-        ball_transform.translation.y = SCREEN_HEIGHT / 2. - BALL_RADIUS - 1. - SCOREBOARD_HEIGHT;
-    }
-
-    if ball_x - BALL_RADIUS <= player_x + PADDLE_WIDTH / 2.
-        && ball_y - BALL_RADIUS <= player_y + PADDLE_HEIGHT / 2.
-        && ball_y + BALL_RADIUS >= player_y - PADDLE_HEIGHT / 2.
-    {
-        // Bounce off the player paddle
-        ball_velocity.0.x = -ball_velocity.0.x;
-        ball_transform.translation.x = player_x + PADDLE_WIDTH / 2. + BALL_RADIUS + 1.;
-    } else if ball_x + BALL_RADIUS >= opponent_x - PADDLE_WIDTH / 2.
-        && ball_y - BALL_RADIUS <= opponent_y + PADDLE_HEIGHT / 2.
-        && ball_y + BALL_RADIUS >= opponent_y - PADDLE_HEIGHT / 2.
-    {
-        // Bounce off the opponent paddle
-        ball_velocity.0.x = -ball_velocity.0.x;
-        ball_transform.translation.x = opponent_x - PADDLE_WIDTH / 2. - BALL_RADIUS - 1.;
-    }
-
-    if player_y + PADDLE_HEIGHT / 2. >= SCREEN_HEIGHT / 2. - SCOREBOARD_HEIGHT {
-        // Prevent the player from going off the top of the screen
-        player_velocity.0.y = 0.;
-        player_transform.translation.y = SCREEN_HEIGHT / 2. - PADDLE_HEIGHT / 2. - SCOREBOARD_HEIGHT;
-    } else if player_y - PADDLE_HEIGHT / 2. <= -SCREEN_HEIGHT / 2. {
-        // Prevent the player from going off the bottom of the screen
-        player_velocity.0.y = 0.;
-        player_transform.translation.y = -SCREEN_HEIGHT / 2. + PADDLE_HEIGHT / 2.;
-    }
-
-    if opponent_y + PADDLE_HEIGHT / 2. >= SCREEN_HEIGHT / 2. - SCOREBOARD_HEIGHT {
-        // Prevent the opponent from going off the top of the screen
-        opponent_transform.translation.y = SCREEN_HEIGHT / 2. - PADDLE_HEIGHT / 2. - SCOREBOARD_HEIGHT;
-    } else if opponent_y - PADDLE_HEIGHT / 2. <= -SCREEN_HEIGHT / 2. {
-        // Prevent the opponent from going off the bottom of the screen
-        opponent_transform.translation.y = -SCREEN_HEIGHT / 2. + PADDLE_HEIGHT / 2.;
+        ControllerSetup::default()
     }
 }
 
-fn check_score_event(
-    mut ball: Query<(&mut Transform, &mut Velocity), With<Ball>>,
-    mut score: ResMut<Score>,
-    mut events: EventReader<Scored>,
-) {
-    let (mut ball_transform, mut ball_velocity) = ball.single_mut();
-
-    let mut rng = rand::thread_rng();
-    let ball_y: f32 = rng.gen();
 
-    for event in events.read() {
-        match event.0 {
-            Scorer::Player => {
-                score.player += 1;
-                ball_transform.translation = Vec3::ZERO;
-                ball_velocity.0 = Vec3::new(-BALL_SPEED, BALL_SPEED / 3. + BALL_SPEED * ball_y, 0.0);
-            }
-            Scorer::Opponent => {
-                score.opponent += 1;
-                ball_transform.translation = Vec3::ZERO;
-                ball_velocity.0 = Vec3::new(BALL_SPEED, -BALL_SPEED / 3. - BALL_SPEED * ball_y, 0.0);
-            }
-        }
-        println!("Score: {} - {}", score.player, score.opponent);
-    }
+fn spawn_camera(mut commands: Commands) {
+    commands.spawn(Camera2dBundle::default());
 }
 
 fn create_scoreboard(
     mut commands: Commands,
+    cameras: Query<Entity, With<Camera2d>>,
 ) {
+    // Laid out in the camera's letterboxed viewport rather than the whole window, so the
+    // scoreboard's strip lines up with the top of the playfield and never covers the bars
     let score_root = commands
         .spawn(NodeBundle {
             style: Style {
                 width: Val::Percent(40.0),
-                height: Val::Percent(5.0),
+                height: Val::Percent(SCOREBOARD_FRACTION * 100.),
                 position_type: PositionType::Absolute,
                 left: Val::Percent(30.0),
                 top: Val::Px(0.0),
@@ -298,6 +119,9 @@ fn create_scoreboard(
             background_color: BackgroundColor(Color::WHITE),
             ..default()
         }).id();
+    if let Ok(camera) = cameras.get_single() {
+        commands.entity(score_root).insert(TargetCamera(camera));
+    }
 
     let player_score_box = commands
         .spawn(NodeBundle {
@@ -0,0 +1,54 @@
+use std::f32::consts::{FRAC_PI_2, FRAC_PI_4};
+
+use bevy::prelude::*;
+use common::particles::{Burst, ParticleSystems, Particles};
+
+use crate::components::{Ball, BallHitPaddle, Velocity, WallBounce};
+
+// Sparks off the paddles and puffs of dust off the walls
+pub struct ParticlesPlugin;
+
+impl Plugin for ParticlesPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins(common::particles::ParticlesPlugin)
+            .add_systems(Update, (emit_paddle_sparks, emit_wall_puffs).before(ParticleSystems));
+    }
+}
+
+fn emit_paddle_sparks(
+    mut hits: EventReader<BallHitPaddle>,
+    balls: Query<&Velocity, With<Ball>>,
+    mut particles: ResMut<Particles>,
+) {
+    for event in hits.read() {
+        // Sprayed off the way the ball is heading now
+        let direction = balls.get(event.ball).map_or(Vec2::Y, |velocity| velocity.0.truncate());
+        particles.burst(Burst {
+            position: event.point,
+            direction,
+            spread: FRAC_PI_4,
+            count: if event.returned { 24 } else { 8 },
+            speed: 450.,
+            lifetime: 0.35,
+            size: 3.,
+            color: Color::srgb(1.0, 0.85, 0.4),
+            ..default()
+        });
+    }
+}
+
+fn emit_wall_puffs(mut bounces: EventReader<WallBounce>, mut particles: ResMut<Particles>) {
+    for event in bounces.read() {
+        particles.burst(Burst {
+            position: event.point,
+            direction: event.normal,
+            spread: FRAC_PI_2,
+            count: 8,
+            speed: 90.,
+            lifetime: 0.5,
+            size: 6.,
+            color: Color::srgba(0.8, 0.8, 0.8, 0.5),
+            ..default()
+        });
+    }
+}
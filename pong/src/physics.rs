@@ -0,0 +1,310 @@
+use bevy::{
+    prelude::*,
+    math::bounding::{Aabb2d, BoundingCircle, BoundingVolume, IntersectsVolume},
+};
+
+use crate::components::Collision;
+use crate::{
+    BALL_RADIUS, BALL_SPEED, BALL_SPEED_STEP, MAX_BALL_SPEED, MAX_BOUNCE_ANGLE, PADDLE_HEIGHT,
+    PADDLE_SPEED, PADDLE_SPIN_ANGLE, PADDLE_WIDTH,
+};
+
+// Where the ball ends up after touching a paddle
+#[derive(Debug, PartialEq)]
+pub struct PaddleHit {
+    pub position: Vec3,
+    pub velocity: Vec3,
+    // True if the ball came off the front face and counts as a return
+    pub returned: bool,
+}
+
+// Ball speed after `hits` paddle hits in the current rally
+pub fn rally_speed(hits: u32) -> f32 {
+    (BALL_SPEED + hits as f32 * BALL_SPEED_STEP).min(MAX_BALL_SPEED)
+}
+
+// Velocity of the ball after bouncing off a paddle's front face.
+// `offset` is where the ball struck the paddle, from -1.0 (bottom edge) to 1.0 (top edge),
+// and `direction` is 1.0 to send the ball right or -1.0 to send it left.
+pub fn paddle_bounce(offset: f32, paddle_velocity: f32, direction: f32, speed: f32) -> Vec3 {
+    // Hitting further from the center sends the ball off at a steeper angle,
+    // and a moving paddle drags the ball along with it
+    let spin = (paddle_velocity / PADDLE_SPEED).clamp(-1., 1.) * PADDLE_SPIN_ANGLE;
+    let angle = (offset.clamp(-1., 1.) * MAX_BOUNCE_ANGLE + spin).clamp(-MAX_BOUNCE_ANGLE, MAX_BOUNCE_ANGLE);
+
+    Vec3::new(direction * angle.cos(), angle.sin(), 0.0) * speed
+}
+
+// Which side of the box the ball hit, if it hit it at all
+pub fn ball_collision(ball: BoundingCircle, bounding_box: Aabb2d) -> Option<Collision> {
+    if !ball.intersects(&bounding_box) {
+        return None;
+    }
+
+    let closest = bounding_box.closest_point(ball.center());
+    let mut offset = ball.center() - closest;
+    if offset == Vec2::ZERO {
+        // The ball's center is already inside the box, so push it out through the nearest face
+        let from_center = ball.center() - bounding_box.center();
+        let depth = bounding_box.half_size() - from_center.abs();
+        offset = if depth.x < depth.y {
+            Vec2::new(from_center.x.signum(), 0.)
+        } else {
+            Vec2::new(0., from_center.y.signum())
+        };
+    }
+
+    let side = if offset.x.abs() > offset.y.abs() {
+        if offset.x < 0. {
+            Collision::Left
+        } else {
+            Collision::Right
+        }
+    } else if offset.y > 0. {
+        Collision::Top
+    } else {
+        Collision::Bottom
+    };
+
+    Some(side)
+}
+
+// Bounces the ball off a wall, returning its new position and velocity if it hit
+pub fn wall_collision(ball_position: Vec3, ball_velocity: Vec3, wall: Aabb2d) -> Option<(Vec3, Vec3)> {
+    let collision = ball_collision(BoundingCircle::new(ball_position.truncate(), BALL_RADIUS), wall)?;
+
+    let mut position = ball_position;
+    let mut velocity = ball_velocity;
+
+    // Reflect only if the velocity is in the opposite direction of the collision,
+    // and move the ball back out of the wall
+    match collision {
+        Collision::Left => {
+            velocity.x = -ball_velocity.x.abs();
+            position.x = wall.min.x - BALL_RADIUS - 1.;
+        }
+        Collision::Right => {
+            velocity.x = ball_velocity.x.abs();
+            position.x = wall.max.x + BALL_RADIUS + 1.;
+        }
+        Collision::Top => {
+            velocity.y = ball_velocity.y.abs();
+            position.y = wall.max.y + BALL_RADIUS + 1.;
+        }
+        Collision::Bottom => {
+            velocity.y = -ball_velocity.y.abs();
+            position.y = wall.min.y - BALL_RADIUS - 1.;
+        }
+    }
+
+    Some((position, velocity))
+}
+
+// Resolves the ball touching a paddle. `facing` is 1.0 for a paddle whose front face points
+// right (the player) or -1.0 for one that points left (the opponent), and `return_speed` is
+// how fast the ball leaves the front face.
+pub fn paddle_collision(
+    ball_position: Vec3,
+    ball_velocity: Vec3,
+    paddle_position: Vec3,
+    paddle_velocity: f32,
+    facing: f32,
+    return_speed: f32,
+) -> Option<PaddleHit> {
+    let half_size = Vec2::new(PADDLE_WIDTH, PADDLE_HEIGHT) / 2.;
+    let collision = ball_collision(
+        BoundingCircle::new(ball_position.truncate(), BALL_RADIUS),
+        Aabb2d::new(paddle_position.truncate(), half_size),
+    )?;
+
+    let mut hit = PaddleHit {
+        position: ball_position,
+        velocity: ball_velocity,
+        returned: false,
+    };
+
+    let front = if facing > 0. { Collision::Right } else { Collision::Left };
+    match collision {
+        side if side == front => {
+            // Only send the ball back if it's moving towards the paddle
+            if ball_velocity.x * facing < 0. {
+                let offset = (ball_position.y - paddle_position.y) / half_size.y;
+                hit.velocity = paddle_bounce(offset, paddle_velocity, facing, return_speed);
+                hit.returned = true;
+            }
+            hit.position.x = paddle_position.x + facing * (half_size.x + BALL_RADIUS + 1.);
+        }
+        Collision::Top => {
+            // Clipped the top edge, deflect upwards (at least as fast as the paddle is moving)
+            hit.velocity.y = ball_velocity.y.abs().max(paddle_velocity);
+            hit.position.y = paddle_position.y + half_size.y + BALL_RADIUS + 1.;
+        }
+        Collision::Bottom => {
+            // Clipped the bottom edge, deflect downwards
+            hit.velocity.y = -ball_velocity.y.abs().max(-paddle_velocity);
+            hit.position.y = paddle_position.y - half_size.y - BALL_RADIUS - 1.;
+        }
+        _ => {
+            // Hit the back of the paddle, keep the ball heading towards the goal behind it
+            if ball_velocity.x * facing > 0. {
+                hit.velocity.x = -ball_velocity.x;
+            }
+            hit.position.x = paddle_position.x - facing * (half_size.x + BALL_RADIUS + 1.);
+        }
+    }
+
+    Some(hit)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PADDLE: Vec3 = Vec3::new(-600., 0., 0.);
+
+    fn hit_player(ball_position: Vec3, ball_velocity: Vec3) -> Option<PaddleHit> {
+        paddle_collision(ball_position, ball_velocity, PADDLE, 0., 1., BALL_SPEED)
+    }
+
+    #[test]
+    fn miss() {
+        let ball = Vec3::new(PADDLE.x + PADDLE_WIDTH / 2. + BALL_RADIUS + 5., 0., 0.);
+        assert_eq!(hit_player(ball, Vec3::new(-BALL_SPEED, 0., 0.)), None);
+    }
+
+    #[test]
+    fn front_face_center_returns_straight() {
+        let ball = Vec3::new(PADDLE.x + PADDLE_WIDTH / 2. + BALL_RADIUS - 2., 0., 0.);
+        let hit = hit_player(ball, Vec3::new(-BALL_SPEED, 0., 0.)).unwrap();
+
+        assert!(hit.returned);
+        assert!((hit.velocity - Vec3::new(BALL_SPEED, 0., 0.)).length() < 0.001);
+        assert!(hit.position.x > PADDLE.x + PADDLE_WIDTH / 2. + BALL_RADIUS);
+    }
+
+    #[test]
+    fn front_face_edge_returns_at_angle() {
+        let ball = Vec3::new(PADDLE.x + PADDLE_WIDTH / 2. + BALL_RADIUS - 2., PADDLE_HEIGHT / 2. - 1., 0.);
+        let hit = hit_player(ball, Vec3::new(-BALL_SPEED, 0., 0.)).unwrap();
+
+        assert!(hit.returned);
+        assert!(hit.velocity.x > 0.);
+        assert!(hit.velocity.y > 0.);
+        assert!((hit.velocity.length() - BALL_SPEED).abs() < 0.001);
+    }
+
+    #[test]
+    fn front_face_ignores_ball_moving_away() {
+        let ball = Vec3::new(PADDLE.x + PADDLE_WIDTH / 2. + BALL_RADIUS - 2., 0., 0.);
+        let velocity = Vec3::new(BALL_SPEED, 50., 0.);
+        let hit = hit_player(ball, velocity).unwrap();
+
+        assert!(!hit.returned);
+        assert_eq!(hit.velocity, velocity);
+    }
+
+    #[test]
+    fn moving_paddle_adds_spin() {
+        let ball = Vec3::new(PADDLE.x + PADDLE_WIDTH / 2. + BALL_RADIUS - 2., 0., 0.);
+        let hit = paddle_collision(ball, Vec3::new(-BALL_SPEED, 0., 0.), PADDLE, PADDLE_SPEED, 1., BALL_SPEED).unwrap();
+
+        assert!(hit.velocity.y > 0.);
+    }
+
+    #[test]
+    fn top_edge_deflects_up_without_returning() {
+        let ball = Vec3::new(PADDLE.x, PADDLE.y + PADDLE_HEIGHT / 2. + BALL_RADIUS - 2., 0.);
+        let hit = hit_player(ball, Vec3::new(-BALL_SPEED, -100., 0.)).unwrap();
+
+        assert!(!hit.returned);
+        assert_eq!(hit.velocity, Vec3::new(-BALL_SPEED, 100., 0.));
+        assert!(hit.position.y > PADDLE.y + PADDLE_HEIGHT / 2. + BALL_RADIUS);
+        assert_eq!(hit.position.x, ball.x);
+    }
+
+    #[test]
+    fn bottom_edge_deflects_down_without_returning() {
+        let ball = Vec3::new(PADDLE.x, PADDLE.y - PADDLE_HEIGHT / 2. - BALL_RADIUS + 2., 0.);
+        let hit = hit_player(ball, Vec3::new(-BALL_SPEED, 100., 0.)).unwrap();
+
+        assert!(!hit.returned);
+        assert_eq!(hit.velocity, Vec3::new(-BALL_SPEED, -100., 0.));
+        assert!(hit.position.y < PADDLE.y - PADDLE_HEIGHT / 2. - BALL_RADIUS);
+    }
+
+    #[test]
+    fn paddle_moving_into_ball_pushes_it() {
+        let ball = Vec3::new(PADDLE.x, PADDLE.y + PADDLE_HEIGHT / 2. + BALL_RADIUS - 2., 0.);
+        let hit = paddle_collision(ball, Vec3::new(-BALL_SPEED, 10., 0.), PADDLE, PADDLE_SPEED, 1., BALL_SPEED).unwrap();
+
+        assert_eq!(hit.velocity.y, PADDLE_SPEED);
+    }
+
+    #[test]
+    fn corner_clip_counts_as_top() {
+        let corner = PADDLE.truncate() + Vec2::new(PADDLE_WIDTH, PADDLE_HEIGHT) / 2.;
+        let ball = (corner + Vec2::new(2., 5.)).extend(0.);
+        let hit = hit_player(ball, Vec3::new(-BALL_SPEED, -BALL_SPEED, 0.)).unwrap();
+
+        assert!(!hit.returned);
+        assert!(hit.velocity.y > 0.);
+    }
+
+    #[test]
+    fn ball_behind_paddle_is_not_returned() {
+        let ball = Vec3::new(PADDLE.x - PADDLE_WIDTH / 2. - BALL_RADIUS + 2., 0., 0.);
+        let velocity = Vec3::new(-BALL_SPEED, 20., 0.);
+        let hit = hit_player(ball, velocity).unwrap();
+
+        assert!(!hit.returned);
+        assert_eq!(hit.velocity, velocity);
+        assert!(hit.position.x < PADDLE.x - PADDLE_WIDTH / 2.);
+    }
+
+    #[test]
+    fn ball_inside_paddle_leaves_nearest_face() {
+        let ball = Vec3::new(PADDLE.x - 2., PADDLE.y + PADDLE_HEIGHT / 2. - 3., 0.);
+        let hit = hit_player(ball, Vec3::new(-BALL_SPEED, -50., 0.)).unwrap();
+
+        assert!(!hit.returned);
+        assert!(hit.velocity.y > 0.);
+        assert!(hit.position.y > PADDLE.y + PADDLE_HEIGHT / 2.);
+    }
+
+    #[test]
+    fn opponent_front_face_returns_left() {
+        let opponent = Vec3::new(600., 0., 0.);
+        let ball = Vec3::new(opponent.x - PADDLE_WIDTH / 2. - BALL_RADIUS + 2., 10., 0.);
+        let hit = paddle_collision(ball, Vec3::new(BALL_SPEED, 0., 0.), opponent, 0., -1., BALL_SPEED).unwrap();
+
+        assert!(hit.returned);
+        assert!(hit.velocity.x < 0.);
+        assert!(hit.position.x < opponent.x - PADDLE_WIDTH / 2. - BALL_RADIUS);
+    }
+
+    #[test]
+    fn wall_reflects_ball_moving_into_it() {
+        let floor = Aabb2d::new(Vec2::new(0., -380.), Vec2::new(640., 20.));
+        let ball = Vec3::new(0., -360. + BALL_RADIUS - 3., 0.);
+        let (position, velocity) = wall_collision(ball, Vec3::new(100., -200., 0.), floor).unwrap();
+
+        assert_eq!(velocity, Vec3::new(100., 200., 0.));
+        assert!(position.y - BALL_RADIUS > -360.);
+    }
+
+    #[test]
+    fn wall_keeps_ball_moving_away() {
+        let floor = Aabb2d::new(Vec2::new(0., -380.), Vec2::new(640., 20.));
+        let ball = Vec3::new(0., -360. + BALL_RADIUS - 3., 0.);
+        let (_, velocity) = wall_collision(ball, Vec3::new(100., 200., 0.), floor).unwrap();
+
+        assert_eq!(velocity, Vec3::new(100., 200., 0.));
+    }
+
+    #[test]
+    fn rally_speed_is_capped() {
+        assert_eq!(rally_speed(0), BALL_SPEED);
+        assert!(rally_speed(1) > BALL_SPEED);
+        assert_eq!(rally_speed(1000), MAX_BALL_SPEED);
+    }
+}
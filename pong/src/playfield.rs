@@ -0,0 +1,21 @@
+pub use common::playfield::*;
+
+// Share of the playfield's height the scoreboard takes up
+pub const SCOREBOARD_FRACTION: f32 = 0.05;
+
+// The scoreboard takes up a strip along the top of pong's playfield
+pub trait Scoreboard {
+    fn scoreboard_height(&self) -> f32;
+    // Top of the area the ball and paddles can move in, just under the scoreboard
+    fn ceiling(&self) -> f32;
+}
+
+impl Scoreboard for Playfield {
+    fn scoreboard_height(&self) -> f32 {
+        self.height * SCOREBOARD_FRACTION
+    }
+
+    fn ceiling(&self) -> f32 {
+        self.top() - self.scoreboard_height()
+    }
+}
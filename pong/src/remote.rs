@@ -0,0 +1,121 @@
+use bevy::prelude::*;
+use common::remote::{RemotePaddle, RemoteServer};
+use serde::{Deserialize, Serialize};
+
+use crate::components::{Ball, OpponentPaddle, PlayerPaddle, Score, Velocity};
+use crate::controller::{drive_paddles, Controller, PaddleView};
+use crate::env::{Action, Observation};
+use crate::PongSet;
+
+// Opens a localhost TCP port that bots can connect to. Every frame each client is sent
+// the game state as a line of JSON:
+//
+//   {"frame": 120, "observation": {...}, "score": [1, 0]}
+//
+// and can send back lines like {"player": "up"} or {"opponent": "down"} to take over
+// a paddle. Paddles go back to their own controllers once every client disconnects.
+pub struct RemoteControlPlugin {
+    pub port: u16,
+}
+
+impl Plugin for RemoteControlPlugin {
+    fn build(&self, app: &mut App) {
+        let server = RemoteServer::bind(self.port)
+            .unwrap_or_else(|error| panic!("Couldn't open remote control port {}: {}", self.port, error));
+        info!("Remote control listening on {}", server.local_addr().unwrap());
+
+        app.insert_resource(RemoteControl {
+            server,
+            frame: 0,
+            player: RemotePaddle::default(),
+            opponent: RemotePaddle::default(),
+        })
+        .init_resource::<RemoteCommands>()
+        .add_systems(
+            FixedUpdate,
+            (accept_remote_clients, read_remote_commands, apply_remote_commands)
+                .chain()
+                .in_set(PongSet::Input)
+                .before(drive_paddles),
+        )
+        .add_systems(Update, send_remote_state);
+    }
+}
+
+#[derive(Resource)]
+struct RemoteControl {
+    server: RemoteServer,
+    frame: u64,
+    player: RemotePaddle<PaddleView, f32>,
+    opponent: RemotePaddle<PaddleView, f32>,
+}
+
+// The latest command for each paddle, `None` if nobody is driving it remotely
+#[derive(Resource, Default, Deserialize)]
+struct RemoteCommands {
+    player: Option<Action>,
+    opponent: Option<Action>,
+}
+
+#[derive(Serialize)]
+struct RemoteState {
+    frame: u64,
+    observation: Observation,
+    score: [u32; 2],
+}
+
+fn accept_remote_clients(mut remote: ResMut<RemoteControl>, mut commands: ResMut<RemoteCommands>) {
+    remote.server.accept();
+    if !remote.server.has_clients() {
+        *commands = RemoteCommands::default();
+    }
+}
+
+fn read_remote_commands(mut remote: ResMut<RemoteControl>, mut commands: ResMut<RemoteCommands>) {
+    for command in remote.server.receive::<RemoteCommands>() {
+        commands.player = command.player.or(commands.player);
+        commands.opponent = command.opponent.or(commands.opponent);
+    }
+}
+
+#[allow(clippy::type_complexity)]
+fn apply_remote_commands(
+    mut remote: ResMut<RemoteControl>,
+    remote_commands: Res<RemoteCommands>,
+    mut player_query: Query<(Entity, Option<&mut Controller>), (With<PlayerPaddle>, Without<OpponentPaddle>)>,
+    mut opponent_query: Query<(Entity, Option<&mut Controller>), With<OpponentPaddle>>,
+    mut commands: Commands,
+) {
+    let remote = &mut *remote;
+    let (player, controller) = player_query.single_mut();
+    let steer = remote_commands.player.map(|action| action.steer());
+    remote.player.steer(steer, player, controller, &mut commands);
+    let (opponent, controller) = opponent_query.single_mut();
+    let steer = remote_commands.opponent.map(|action| action.steer());
+    remote.opponent.steer(steer, opponent, controller, &mut commands);
+}
+
+fn send_remote_state(
+    mut remote: ResMut<RemoteControl>,
+    ball_query: Query<(&Transform, &Velocity), With<Ball>>,
+    player_query: Query<(&Transform, &Velocity), With<PlayerPaddle>>,
+    opponent_query: Query<(&Transform, &Velocity), With<OpponentPaddle>>,
+    score: Res<Score>,
+) {
+    remote.frame += 1;
+    if !remote.server.has_clients() {
+        return;
+    }
+
+    let motion = |(transform, velocity): (&Transform, &Velocity)| (transform.translation, velocity.0);
+    let state = RemoteState {
+        frame: remote.frame,
+        observation: Observation::new(
+            motion(ball_query.single()),
+            motion(player_query.single()),
+            motion(opponent_query.single()),
+        ),
+        score: [score.player, score.opponent],
+    };
+    remote.server.send(&state);
+}
@@ -0,0 +1,57 @@
+use bevy::prelude::*;
+use common::title::TitleScreenPlugin;
+
+use crate::ai::AiKind;
+use crate::components::Screen;
+use crate::controller::ControllerSetup;
+use crate::{start_match, PongSet};
+
+pub use common::title::AnyInput;
+
+// The title screen, with the computer playing itself in the demo games
+pub struct TitlePlugin {
+    // Who plays when a game is started from the title screen
+    pub players: fn() -> ControllerSetup,
+}
+
+impl Plugin for TitlePlugin {
+    fn build(&self, app: &mut App) {
+        let players = self.players;
+
+        app.init_state::<Screen>()
+            .enable_state_scoped_entities::<Screen>()
+            .add_plugins(TitleScreenPlugin::<Screen>::new("PONG", 120.))
+            // The field stays frozen behind the other screens
+            .configure_sets(
+                FixedUpdate,
+                (PongSet::Input, PongSet::Simulation).run_if(in_state(Screen::Demo).or_else(in_state(Screen::Playing))),
+            )
+            .add_systems(
+                OnEnter(Screen::Demo),
+                (
+                    |mut commands: Commands| {
+                        commands.insert_resource(ControllerSetup::new(AiKind::Predict, AiKind::Follow));
+                    },
+                    start_match,
+                )
+                    .chain(),
+            )
+            .add_systems(
+                OnEnter(Screen::Playing),
+                (
+                    move |mut commands: Commands| {
+                        commands.insert_resource(players());
+                    },
+                    start_match,
+                )
+                    .chain(),
+            )
+            .add_systems(Update, leave_game.run_if(in_state(Screen::Playing)));
+    }
+}
+
+fn leave_game(key: Res<ButtonInput<KeyCode>>, mut next_screen: ResMut<NextState<Screen>>) {
+    if key.just_pressed(KeyCode::Escape) {
+        next_screen.set(Screen::Title);
+    }
+}